@@ -0,0 +1,34 @@
+/// Output of the ALU for one computation.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AluOutput {
+    pub out: u16,
+    /// out == 0
+    pub zr: bool,
+    /// out < 0
+    pub ng: bool,
+}
+
+/// The Hack ALU, bit for bit as in `02/ALU.hdl`. `control` holds the six
+/// control bits `zx nx zy ny f no` with `zx` as the most significant bit,
+/// which is how they appear in a C-instruction (`c1..c6`).
+pub fn alu(x: u16, y: u16, control: u16) -> AluOutput {
+    let zx = control & 0b100000 != 0;
+    let nx = control & 0b010000 != 0;
+    let zy = control & 0b001000 != 0;
+    let ny = control & 0b000100 != 0;
+    let f = control & 0b000010 != 0;
+    let no = control & 0b000001 != 0;
+
+    let x = if zx { 0 } else { x };
+    let x = if nx { !x } else { x };
+    let y = if zy { 0 } else { y };
+    let y = if ny { !y } else { y };
+    let out = if f { x.wrapping_add(y) } else { x & y };
+    let out = if no { !out } else { out };
+
+    AluOutput {
+        out,
+        zr: out == 0,
+        ng: out & 0x8000 != 0,
+    }
+}
@@ -0,0 +1,272 @@
+use crate::alu::alu;
+use std::fmt;
+
+pub const ROM_SIZE: usize = 0x8000;
+pub const RAM_SIZE: usize = 0x4000;
+pub const SCREEN: usize = 0x4000;
+pub const SCREEN_SIZE: usize = 0x2000;
+pub const KBD: usize = 0x6000;
+
+#[derive(Debug)]
+pub enum EmulatorError {
+    ProgramTooLarge(usize),
+    InvalidWord(usize, String),
+}
+
+impl fmt::Display for EmulatorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EmulatorError::ProgramTooLarge(n) => write!(
+                f,
+                "Program has {} instructions but the ROM holds {}",
+                n, ROM_SIZE
+            ),
+            EmulatorError::InvalidWord(line, word) => write!(
+                f,
+                "Line {}: '{}' is not a 16-bit binary word",
+                line, word
+            ),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RunOutcome {
+    /// The program reached a halting loop such as `(END) @END 0;JMP`
+    Halted { cycles: u64 },
+    /// The cycle budget ran out first
+    CycleLimit { cycles: u64 },
+}
+
+/// Parses the `.hack` text format, one line of 16 `0`/`1` characters per word.
+pub fn parse_hack(text: &str) -> Result<Vec<u16>, EmulatorError> {
+    text.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| {
+            let word = line.trim();
+            if word.len() != 16 {
+                return Err(EmulatorError::InvalidWord(i + 1, word.to_string()));
+            }
+
+            u16::from_str_radix(word, 2)
+                .map_err(|_| EmulatorError::InvalidWord(i + 1, word.to_string()))
+        })
+        .collect()
+}
+
+/// The Hack computer of `05/Computer.hdl`: a CPU wired to a 32K instruction
+/// ROM and the data memory of `05/Memory.hdl` (16K RAM, 8K screen map and
+/// the keyboard register). Each [`Computer::step`] is one clock cycle.
+pub struct Computer {
+    rom: Vec<u16>,
+    ram: Vec<u16>,
+    screen: Vec<u16>,
+    keyboard: u16,
+    a: u16,
+    d: u16,
+    pc: u16,
+    cycles: u64,
+}
+
+impl Default for Computer {
+    fn default() -> Self {
+        Self {
+            rom: vec![0; ROM_SIZE],
+            ram: vec![0; RAM_SIZE],
+            screen: vec![0; SCREEN_SIZE],
+            keyboard: 0,
+            a: 0,
+            d: 0,
+            pc: 0,
+            cycles: 0,
+        }
+    }
+}
+
+impl Computer {
+    pub fn new(program: &[u16]) -> Result<Self, EmulatorError> {
+        let mut computer = Self::default();
+        computer.load_rom(program)?;
+
+        Ok(computer)
+    }
+
+    /// Replaces the ROM contents; unused addresses are filled with `0`.
+    pub fn load_rom(&mut self, program: &[u16]) -> Result<(), EmulatorError> {
+        if program.len() > ROM_SIZE {
+            return Err(EmulatorError::ProgramTooLarge(program.len()));
+        }
+
+        self.rom.fill(0);
+        self.rom[..program.len()].copy_from_slice(program);
+
+        Ok(())
+    }
+
+    /// Asserts the CPU's reset input: PC goes back to 0, registers and
+    /// memory keep their values.
+    pub fn reset(&mut self) {
+        self.pc = 0;
+    }
+
+    pub fn a(&self) -> u16 {
+        self.a
+    }
+
+    pub fn d(&self) -> u16 {
+        self.d
+    }
+
+    pub fn pc(&self) -> u16 {
+        self.pc
+    }
+
+    pub fn cycles(&self) -> u64 {
+        self.cycles
+    }
+
+    pub fn set_a(&mut self, value: u16) {
+        self.a = value;
+    }
+
+    pub fn set_d(&mut self, value: u16) {
+        self.d = value;
+    }
+
+    pub fn set_pc(&mut self, value: u16) {
+        self.pc = value & 0x7FFF;
+    }
+
+    pub fn rom(&self) -> &[u16] {
+        &self.rom
+    }
+
+    pub fn ram(&self) -> &[u16] {
+        &self.ram
+    }
+
+    pub fn screen(&self) -> &[u16] {
+        &self.screen
+    }
+
+    pub fn keyboard(&self) -> u16 {
+        self.keyboard
+    }
+
+    /// Sets the scan code the keyboard register reports, 0 for no key.
+    pub fn set_keyboard(&mut self, key: u16) {
+        self.keyboard = key;
+    }
+
+    /// Reads data memory the way the CPU sees it through `inM`.
+    pub fn peek(&self, address: usize) -> u16 {
+        match address & 0x7FFF {
+            addr if addr < SCREEN => self.ram[addr],
+            addr if addr < KBD => self.screen[addr - SCREEN],
+            _ => self.keyboard,
+        }
+    }
+
+    /// Writes data memory from the host side. Unlike a CPU write, this can
+    /// also set the keyboard register, so tests can simulate key presses.
+    pub fn poke(&mut self, address: usize, value: u16) {
+        match address & 0x7FFF {
+            addr if addr < SCREEN => self.ram[addr] = value,
+            addr if addr < KBD => self.screen[addr - SCREEN] = value,
+            _ => self.keyboard = value,
+        }
+    }
+
+    fn write_memory(&mut self, address: usize, value: u16) {
+        // The keyboard is read-only for the CPU
+        if address & 0x7FFF < KBD {
+            self.poke(address, value);
+        }
+    }
+
+    /// Executes the instruction at PC, as one clock cycle of the CPU.
+    pub fn step(&mut self) {
+        let instruction = self.rom[self.pc as usize];
+        self.cycles += 1;
+
+        // A-instruction: load the 15-bit constant into A
+        if instruction & 0x8000 == 0 {
+            self.a = instruction;
+            self.pc = (self.pc + 1) & 0x7FFF;
+            return;
+        }
+
+        let uses_m = instruction & 0x1000 != 0;
+        let control = (instruction >> 6) & 0b111111;
+        let dest = (instruction >> 3) & 0b111;
+        let jump = instruction & 0b111;
+
+        // Everything below is computed from the registers as they were at
+        // the start of the cycle, e.g. AM=M+1 writes M at the old A
+        let address = (self.a & 0x7FFF) as usize;
+        let y = if uses_m { self.peek(address) } else { self.a };
+        let result = alu(self.d, y, control);
+
+        let jump_taken = (jump & 0b100 != 0 && result.ng)
+            || (jump & 0b010 != 0 && result.zr)
+            || (jump & 0b001 != 0 && !result.ng && !result.zr);
+        let jump_target = self.a & 0x7FFF;
+
+        if dest & 0b001 != 0 {
+            self.write_memory(address, result.out);
+        }
+        if dest & 0b010 != 0 {
+            self.d = result.out;
+        }
+        if dest & 0b100 != 0 {
+            self.a = result.out;
+        }
+
+        self.pc = if jump_taken {
+            jump_target
+        } else {
+            (self.pc + 1) & 0x7FFF
+        };
+    }
+
+    /// True when PC sits in a loop that can never change the machine state:
+    /// an unconditional jump with no destination, either to itself or back
+    /// to the `@label` just before it.
+    pub fn is_halted(&self) -> bool {
+        let pc = self.pc as usize;
+        let instruction = self.rom[pc];
+
+        let unconditional_jump = instruction & 0x8000 != 0
+            && instruction & 0b111 == 0b111
+            && (instruction >> 3) & 0b111 == 0;
+        if !unconditional_jump {
+            return false;
+        }
+
+        let target = (self.a & 0x7FFF) as usize;
+        target == pc || (pc > 0 && target == pc - 1 && self.rom[pc - 1] as usize == pc - 1)
+    }
+
+    /// Runs until a halting loop is reached or `max_cycles` cycles have run.
+    pub fn run(&mut self, max_cycles: u64) -> RunOutcome {
+        for _ in 0..max_cycles {
+            if self.is_halted() {
+                return RunOutcome::Halted {
+                    cycles: self.cycles,
+                };
+            }
+            self.step();
+        }
+
+        if self.is_halted() {
+            RunOutcome::Halted {
+                cycles: self.cycles,
+            }
+        } else {
+            RunOutcome::CycleLimit {
+                cycles: self.cycles,
+            }
+        }
+    }
+}
@@ -0,0 +1,4 @@
+pub mod alu;
+pub mod computer;
+
+pub use computer::{Computer, EmulatorError, RunOutcome, parse_hack};
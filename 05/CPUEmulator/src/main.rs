@@ -0,0 +1,61 @@
+use cpu_emulator::{Computer, RunOutcome, parse_hack};
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::process;
+
+const DEFAULT_MAX_CYCLES: u64 = 1_000_000;
+
+fn load_program(path: &str) -> Vec<u16> {
+    let source = fs::read_to_string(path).expect("read file");
+
+    // .asm files are assembled in-process, anything else is read as .hack
+    if Path::new(path).extension().is_some_and(|ext| ext == "asm") {
+        match hack_asm::assemble_with_options(
+            &source,
+            &hack_asm::Options {
+                file_name: path.to_string(),
+                ..hack_asm::Options::default()
+            },
+        ) {
+            Ok(program) => program.words,
+            Err(diagnostics) => {
+                eprintln!("{}", diagnostics);
+                process::exit(1);
+            }
+        }
+    } else {
+        parse_hack(&source).unwrap_or_else(|e| {
+            eprintln!("{}: {}", path, e);
+            process::exit(1);
+        })
+    }
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    if args.len() != 2 && args.len() != 3 {
+        println!("Usage: cargo run -- <program.hack|program.asm> [max cycles]");
+        return;
+    }
+
+    let max_cycles: u64 = match args.get(2) {
+        Some(cycles) => cycles.parse().expect("max cycles"),
+        None => DEFAULT_MAX_CYCLES,
+    };
+
+    let mut computer = Computer::new(&load_program(&args[1])).unwrap_or_else(|e| {
+        eprintln!("{}: {}", args[1], e);
+        process::exit(1);
+    });
+
+    match computer.run(max_cycles) {
+        RunOutcome::Halted { cycles } => println!("Halted after {} cycles", cycles),
+        RunOutcome::CycleLimit { cycles } => println!("Stopped after {} cycles", cycles),
+    }
+
+    println!("A={} D={} PC={}", computer.a(), computer.d(), computer.pc());
+    for (i, value) in computer.ram()[..16].iter().enumerate() {
+        println!("RAM[{}]={}", i, *value as i16);
+    }
+}
@@ -0,0 +1,90 @@
+use crate::parser::ParserError;
+use hack_core::encoding::{COMP_TABLE, DEST_TABLE, JUMP_TABLE};
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    // Optimal string alignment distance, so a swapped pair like JPM -> JMP costs one edit
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let mut d: Vec<Vec<usize>> = vec![vec![0; b.len() + 1]; a.len() + 1];
+
+    for (i, row) in d.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, cell) in d[0].iter_mut().enumerate() {
+        *cell = j;
+    }
+
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            d[i][j] = (d[i - 1][j] + 1)
+                .min(d[i][j - 1] + 1)
+                .min(d[i - 1][j - 1] + cost);
+
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                d[i][j] = d[i][j].min(d[i - 2][j - 2] + 1);
+            }
+        }
+    }
+
+    d[a.len()][b.len()]
+}
+
+pub struct Code;
+
+impl Code {
+    fn lookup(&self, table: &[(&'static str, &'static str)], mnemonic: &str) -> Option<&'static str> {
+        table
+            .iter()
+            .find(|(name, _)| *name == mnemonic)
+            .map(|(_, bits)| *bits)
+    }
+
+    fn suggest(&self, table: &[(&'static str, &'static str)], mnemonic: &str) -> Option<&'static str> {
+        // Closest mnemonic within two edits, preferring the canonical (first listed) spelling
+        table
+            .iter()
+            .map(|(name, _)| (edit_distance(mnemonic, name), *name))
+            .filter(|(distance, name)| *distance <= 2 && *name != "null")
+            .min_by_key(|(distance, _)| *distance)
+            .map(|(_, name)| name)
+    }
+
+    fn reverse_lookup(&self, table: &[(&'static str, &'static str)], bits: &str) -> Option<&'static str> {
+        // The canonical spelling is listed first, so it wins over commutative aliases
+        table
+            .iter()
+            .find(|(_, binary)| *binary == bits)
+            .map(|(name, _)| *name)
+    }
+
+    pub fn comp_to_binary(&self, comp_str: &str) -> Result<&'static str, ParserError> {
+        self.lookup(&COMP_TABLE, comp_str).ok_or_else(|| {
+            ParserError::InvalidComp(comp_str.to_string(), self.suggest(&COMP_TABLE, comp_str))
+        })
+    }
+
+    pub fn dest_to_binary(&self, dest_str: &str) -> Result<&'static str, ParserError> {
+        self.lookup(&DEST_TABLE, dest_str).ok_or_else(|| {
+            ParserError::InvalidDest(dest_str.to_string(), self.suggest(&DEST_TABLE, dest_str))
+        })
+    }
+
+    pub fn jump_to_binary(&self, jump_str: &str) -> Result<&'static str, ParserError> {
+        self.lookup(&JUMP_TABLE, jump_str).ok_or_else(|| {
+            ParserError::InvalidJump(jump_str.to_string(), self.suggest(&JUMP_TABLE, jump_str))
+        })
+    }
+
+    pub fn binary_to_comp(&self, bits: &str) -> Option<&'static str> {
+        self.reverse_lookup(&COMP_TABLE, bits)
+    }
+
+    pub fn binary_to_dest(&self, bits: &str) -> Option<&'static str> {
+        self.reverse_lookup(&DEST_TABLE, bits)
+    }
+
+    pub fn binary_to_jump(&self, bits: &str) -> Option<&'static str> {
+        self.reverse_lookup(&JUMP_TABLE, bits)
+    }
+}
@@ -0,0 +1,198 @@
+use crate::code::Code;
+use crate::symbol_file::SymbolFile;
+use hack_core::diagnostics::{Diagnostic, Diagnostics};
+use hack_core::symbols::STARTING_VARIABLE_ADDRESS;
+
+#[derive(Debug)]
+pub enum DisassemblerError {
+    MalformedWord,
+    BadPrefix,
+    UnknownComp(String),
+}
+
+impl DisassemblerError {
+    /// Character range of the offending bits within the 16-character word.
+    fn span(&self) -> (usize, usize) {
+        match self {
+            DisassemblerError::MalformedWord => (0, 16),
+            DisassemblerError::BadPrefix => (1, 2),
+            DisassemblerError::UnknownComp(_) => (3, 7),
+        }
+    }
+}
+
+impl std::fmt::Display for DisassemblerError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DisassemblerError::MalformedWord => write!(f, "Expected 16 binary digits"),
+            DisassemblerError::BadPrefix => {
+                write!(f, "C-instructions must start with 111")
+            }
+            DisassemblerError::UnknownComp(bits) => {
+                write!(f, "No comp mnemonic encodes as '{}'", bits)
+            }
+        }
+    }
+}
+
+/// Decodes a C-instruction into `dest=comp;jump`, leaving out null fields.
+fn decode_c_instruction(word: &str) -> Result<String, DisassemblerError> {
+    let code = Code;
+    if &word[1..3] != "11" {
+        return Err(DisassemblerError::BadPrefix);
+    }
+
+    let comp = code
+        .binary_to_comp(&word[3..10])
+        .ok_or_else(|| DisassemblerError::UnknownComp(word[3..10].to_string()))?;
+    // Every 3-bit pattern is a valid dest and jump
+    let dest = code.binary_to_dest(&word[10..13]).unwrap_or("null");
+    let jump = code.binary_to_jump(&word[13..16]).unwrap_or("null");
+
+    let mut instruction = String::new();
+    if dest != "null" {
+        instruction.push_str(dest);
+        instruction.push('=');
+    }
+    instruction.push_str(comp);
+    if jump != "null" {
+        instruction.push(';');
+        instruction.push_str(jump);
+    }
+
+    Ok(instruction)
+}
+
+fn parse_word(line: &str) -> Result<u16, DisassemblerError> {
+    if line.len() != 16 || !line.chars().all(|c| c == '0' || c == '1') {
+        return Err(DisassemblerError::MalformedWord);
+    }
+
+    u16::from_str_radix(line, 2).map_err(|_| DisassemblerError::MalformedWord)
+}
+
+/// Name for the value loaded by `@value`: a label if the next instruction
+/// jumps, otherwise preferably a variable. Variables are only named in the
+/// order the assembler allocates them, so reassembling the output gives
+/// every variable its original address.
+fn symbol_name<'a>(
+    symbols: &'a SymbolFile,
+    value: usize,
+    next: Option<u16>,
+    next_variable: &mut usize,
+) -> Option<&'a str> {
+    let jumps =
+        next.is_some_and(|word| word & 0x8000 != 0 && word & 0x1000 == 0 && word & 0x7 != 0);
+    if jumps {
+        return symbols.label_at(value);
+    }
+
+    let variable = symbols
+        .variable_at(value)
+        .filter(|_| value <= *next_variable);
+    if variable.is_some() && value == *next_variable {
+        *next_variable += 1;
+    }
+
+    variable.or_else(|| symbols.label_at(value))
+}
+
+pub struct Disassembler<'a> {
+    file_name: String,
+    symbols: Option<&'a SymbolFile>,
+}
+
+impl<'a> Disassembler<'a> {
+    pub fn new(file_name: String, symbols: Option<&'a SymbolFile>) -> Self {
+        Self { file_name, symbols }
+    }
+
+    fn diagnostic(&self, line_number: usize, line: &str, error: DisassemblerError) -> Diagnostic {
+        let (start, len) = error.span();
+        let text = line.get(start..start + len).unwrap_or(line);
+        let mut diagnostic =
+            Diagnostic::new(&self.file_name, line_number, line, text, error.to_string());
+        if line.len() == 16 {
+            diagnostic.column = start + 1;
+        }
+
+        diagnostic
+    }
+
+    /// Turns `.hack` text back into assembly, one instruction per word.
+    pub fn disassemble(&self, source: &str) -> Result<String, Diagnostics> {
+        let mut diagnostics = Diagnostics::new();
+        let lines: Vec<(usize, &str)> = source
+            .lines()
+            .enumerate()
+            .map(|(i, line)| (i + 1, line.trim()))
+            .filter(|(_, line)| !line.is_empty())
+            .collect();
+
+        // Decoded up front so `@value` can look at the instruction after it
+        let words: Vec<Result<u16, DisassemblerError>> =
+            lines.iter().map(|(_, line)| parse_word(line)).collect();
+
+        let mut output = String::new();
+        let mut next_variable = STARTING_VARIABLE_ADDRESS;
+        for (address, ((line_number, line), word)) in
+            lines.into_iter().zip(words.iter()).enumerate()
+        {
+            if let Some(symbols) = self.symbols {
+                for (name, _) in symbols.labels.iter().filter(|(_, value)| *value == address) {
+                    output.push_str(&format!("({})\n", name));
+                }
+            }
+
+            let word = match word {
+                Ok(word) => *word,
+                Err(_) => {
+                    diagnostics.push(self.diagnostic(
+                        line_number,
+                        line,
+                        DisassemblerError::MalformedWord,
+                    ));
+                    continue;
+                }
+            };
+
+            if word & 0x8000 == 0 {
+                let value = word as usize;
+                let next = words
+                    .get(address + 1)
+                    .and_then(|word| word.as_ref().ok())
+                    .copied();
+                match self
+                    .symbols
+                    .and_then(|symbols| symbol_name(symbols, value, next, &mut next_variable))
+                {
+                    Some(name) => output.push_str(&format!("  @{}\n", name)),
+                    None => output.push_str(&format!("  @{}\n", value)),
+                }
+                continue;
+            }
+
+            match decode_c_instruction(line) {
+                Ok(instruction) => output.push_str(&format!("  {}\n", instruction)),
+                Err(error) => diagnostics.push(self.diagnostic(line_number, line, error)),
+            }
+        }
+
+        // Labels may also point just past the last instruction
+        if let Some(symbols) = self.symbols {
+            for (name, _) in symbols
+                .labels
+                .iter()
+                .filter(|(_, value)| *value == words.len())
+            {
+                output.push_str(&format!("({})\n", name));
+            }
+        }
+
+        if !diagnostics.is_empty() {
+            return Err(diagnostics);
+        }
+
+        Ok(output)
+    }
+}
@@ -0,0 +1,247 @@
+use std::collections::HashMap;
+
+mod code;
+mod disassembler;
+mod parser;
+mod preprocessor;
+mod symbol_file;
+
+pub use hack_core::diagnostics::{Diagnostic, Diagnostics};
+pub use symbol_file::{SymbolFile, SymbolFileError};
+
+/// An assembled Hack program: one 16-bit word per ROM address, plus the
+/// final symbol table (predefined symbols, labels and variables).
+#[derive(Debug, Clone, PartialEq)]
+pub struct Program {
+    pub words: Vec<u16>,
+    pub symbols: HashMap<String, usize>,
+    /// User-defined labels and variables and the source line of each word,
+    /// as written by `--sym`
+    pub symbol_file: SymbolFile,
+    /// Each label and the source line that defined it; labels from a macro
+    /// expansion or include belong to the line that pulled them in
+    pub label_origins: Vec<(String, usize)>,
+    /// Problems that did not stop assembly, such as labels differing only by case
+    pub warnings: Diagnostics,
+}
+
+/// Ways to write an assembled program out.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OutputFormat {
+    /// `.hack` text, 16 `0`/`1` characters per line
+    Hack,
+    /// Raw big-endian 16-bit words
+    Binary,
+    /// Intel HEX, byte addressed, big-endian words
+    IntelHex,
+    /// Text image for Verilog's `$readmemb`
+    Readmemb,
+    /// Logisim memory image: a `v2.0 raw` header and one hex word per line
+    Logisim,
+}
+
+impl OutputFormat {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "hack" => Some(OutputFormat::Hack),
+            "bin" | "binary" => Some(OutputFormat::Binary),
+            "hex" | "ihex" => Some(OutputFormat::IntelHex),
+            "readmemb" => Some(OutputFormat::Readmemb),
+            "logisim" => Some(OutputFormat::Logisim),
+            _ => None,
+        }
+    }
+}
+
+impl Program {
+    /// Renders the program in the `.hack` text format, one line of 16 `0`/`1`
+    /// characters per instruction.
+    pub fn to_hack(&self) -> String {
+        self.words
+            .iter()
+            .map(|word| format!("{:016b}\n", word))
+            .collect()
+    }
+
+    /// Output file contents in the given format.
+    pub fn to_format(&self, format: OutputFormat) -> Vec<u8> {
+        match format {
+            OutputFormat::Hack => self.to_hack().into_bytes(),
+            OutputFormat::Binary => self.to_binary(),
+            OutputFormat::IntelHex => self.to_intel_hex().into_bytes(),
+            OutputFormat::Readmemb => self.to_readmemb().into_bytes(),
+            OutputFormat::Logisim => self.to_logisim().into_bytes(),
+        }
+    }
+
+    /// Raw image, two bytes per instruction, most significant byte first.
+    pub fn to_binary(&self) -> Vec<u8> {
+        self.words.iter().flat_map(|word| word.to_be_bytes()).collect()
+    }
+
+    /// Intel HEX data records of 16 bytes (8 instructions), addressed by
+    /// byte, followed by the end-of-file record. The full 32K-word ROM fits
+    /// the 16-bit record address, so no extended address records are needed.
+    pub fn to_intel_hex(&self) -> String {
+        let bytes = self.to_binary();
+        let mut hex = String::new();
+
+        for (i, chunk) in bytes.chunks(16).enumerate() {
+            let address = (i * 16) as u16;
+            let mut record = vec![chunk.len() as u8];
+            record.extend(address.to_be_bytes());
+            record.push(0x00);
+            record.extend(chunk);
+
+            // Two's complement of the byte sum, so the whole record sums to zero
+            let checksum = record.iter().fold(0u8, |sum, byte| sum.wrapping_add(*byte)).wrapping_neg();
+            record.push(checksum);
+
+            hex.push(':');
+            hex.extend(record.iter().map(|byte| format!("{:02X}", byte)));
+            hex.push('\n');
+        }
+
+        hex.push_str(":00000001FF\n");
+        hex
+    }
+
+    /// One binary word per line as read by `$readmemb`, with the ROM address
+    /// of each word in a trailing comment.
+    pub fn to_readmemb(&self) -> String {
+        let mut image = String::from("// Hack ROM image, load with $readmemb\n@0\n");
+        for (address, word) in self.words.iter().enumerate() {
+            image.push_str(&format!("{:016b} // {:04X}\n", word, address));
+        }
+
+        image
+    }
+
+    /// The image Logisim's memory components load, which takes no comments
+    /// or address directives.
+    pub fn to_logisim(&self) -> String {
+        let mut image = String::from("v2.0 raw\n");
+        for word in &self.words {
+            image.push_str(&format!("{:04x}\n", word));
+        }
+
+        image
+    }
+
+    /// Renders a listing of `source` (the text this program was assembled
+    /// from): every source line, comments included, next to the ROM address
+    /// and the hex and binary encoding of the instruction it produced. Label
+    /// lines show the address they resolve to; labels a macro call or include
+    /// defines get rows of their own under that line.
+    pub fn to_listing(&self, source: &str) -> String {
+        let mut listing = String::from("ADDR   HEX   BINARY            SOURCE\n");
+        let mut entries = self.symbol_file.source_lines.iter().peekable();
+        let mut labels = self.label_origins.iter().peekable();
+
+        for (i, line) in source.lines().enumerate() {
+            let line = line.trim_end();
+
+            let mut addresses: Vec<usize> = Vec::new();
+            while let Some((address, _)) = entries.next_if(|(_, source_line)| *source_line == i + 1) {
+                addresses.push(*address);
+            }
+            let mut line_labels: Vec<(&str, usize)> = Vec::new();
+            while let Some((name, _)) = labels.next_if(|(_, source_line)| *source_line == i + 1) {
+                line_labels.push((name, self.symbols[name]));
+            }
+
+            // A plain `(LABEL)` line; labels inside a .macro definition aren't
+            // defined until the macro is called, so they get no address
+            if addresses.is_empty() && line_labels.len() == 1 && self.is_label_line(line) {
+                let row = format!("{:04}{:27}{}", line_labels[0].1, "", line);
+                listing.push_str(row.trim_end());
+                listing.push('\n');
+                continue;
+            }
+
+            let row = match addresses.first() {
+                Some(&address) => self.word_row(address, line),
+                None => format!("{:31}{}", "", line),
+            };
+            listing.push_str(row.trim_end());
+            listing.push('\n');
+
+            // Macro calls and includes produce several words from one line,
+            // with their labels in between
+            // (address, is a word, row): a label comes before the word it names
+            let mut rows: Vec<(usize, bool, String)> = line_labels
+                .iter()
+                .map(|(name, address)| (*address, false, format!("{:04}{:27}({})", address, "", name)))
+                .collect();
+            rows.extend(
+                addresses
+                    .iter()
+                    .skip(1)
+                    .map(|&address| (address, true, self.word_row(address, ""))),
+            );
+            rows.sort_by_key(|(address, is_word, _)| (*address, *is_word));
+
+            for (_, _, row) in rows {
+                listing.push_str(row.trim_end());
+                listing.push('\n');
+            }
+        }
+
+        listing
+    }
+
+    fn word_row(&self, address: usize, line: &str) -> String {
+        let word = self.words[address];
+        format!("{:04}   {:04X}  {:016b}  {}", address, word, word, line)
+    }
+
+    fn is_label_line(&self, line: &str) -> bool {
+        let code = line.split_once("//").map_or(line, |(before, _)| before).trim();
+        code.starts_with('(') && code.ends_with(')')
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Options {
+    /// Name reported in diagnostics
+    pub file_name: String,
+    /// Accept `@0x...` and `@0b...` literals in A-instructions
+    pub extended_literals: bool,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            file_name: "<source>".to_string(),
+            extended_literals: false,
+        }
+    }
+}
+
+/// Assembles Hack assembly source into a [`Program`] with the default options.
+pub fn assemble(source: &str) -> Result<Program, Diagnostics> {
+    assemble_with_options(source, &Options::default())
+}
+
+pub fn assemble_with_options(source: &str, options: &Options) -> Result<Program, Diagnostics> {
+    let mut parser = parser::Parser::new(options.file_name.clone(), options.extended_literals);
+    let words = parser.parse(source)?;
+
+    Ok(Program {
+        words,
+        symbols: parser.symbols().clone(),
+        symbol_file: parser.symbol_file().clone(),
+        label_origins: parser.label_origins().to_vec(),
+        warnings: parser.warnings().clone(),
+    })
+}
+
+/// Turns `.hack` text back into assembly. With a symbol file, labels are
+/// re-inserted and `@value` loads use label and variable names again.
+pub fn disassemble(
+    source: &str,
+    file_name: &str,
+    symbols: Option<&SymbolFile>,
+) -> Result<String, Diagnostics> {
+    disassembler::Disassembler::new(file_name.to_string(), symbols).disassemble(source)
+}
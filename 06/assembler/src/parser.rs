@@ -0,0 +1,421 @@
+use crate::code::Code;
+use crate::preprocessor::{Preprocessor, SourceLine};
+use crate::symbol_file::SymbolFile;
+use hack_core::diagnostics::{Diagnostic, Diagnostics};
+use hack_core::encoding::MAX_ADDRESS;
+use hack_core::symbols::{STARTING_VARIABLE_ADDRESS, predefined_symbols};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use std::collections::HashMap;
+
+static SYMBOL_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^[A-Za-z_.$:]+[A-Za-z_.$:0-9]*$").unwrap());
+static ADDRESS_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"^[0-9]+$").unwrap());
+static HEX_ADDRESS_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"^0[xX][0-9A-Fa-f]+$").unwrap());
+static BIN_ADDRESS_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"^0[bB][01]+$").unwrap());
+
+#[derive(Clone, Debug, PartialEq)]
+#[allow(clippy::enum_variant_names)]
+pub enum InstructionType {
+    LInstruction,
+    AInstruction,
+    CInstruction,
+}
+
+#[derive(Debug)]
+pub enum ParserError {
+    EmptyAddress,
+    InvalidNumber(std::num::ParseIntError),
+    InvalidFormat,
+    InvalidSymbol,
+    InvalidComp(String, Option<&'static str>),
+    InvalidDest(String, Option<&'static str>),
+    InvalidJump(String, Option<&'static str>),
+    AddressOutOfRange(String),
+    ExtendedLiteralDisabled(String),
+    DuplicateLabel(String, String),
+    ReservedLabel(String),
+}
+
+impl ParserError {
+    fn text(&self) -> Option<&str> {
+        match self {
+            ParserError::InvalidComp(text, _)
+            | ParserError::InvalidDest(text, _)
+            | ParserError::InvalidJump(text, _) => Some(text),
+            ParserError::AddressOutOfRange(text)
+            | ParserError::ExtendedLiteralDisabled(text)
+            | ParserError::DuplicateLabel(text, _)
+            | ParserError::ReservedLabel(text) => Some(text),
+            _ => None,
+        }
+    }
+}
+
+fn write_mnemonic_error(
+    f: &mut std::fmt::Formatter<'_>,
+    field: &str,
+    mnemonic: &str,
+    suggestion: &Option<&str>,
+) -> std::fmt::Result {
+    write!(f, "Unknown {} mnemonic '{}'", field, mnemonic)?;
+    if let Some(suggestion) = suggestion {
+        write!(f, ", did you mean '{}'?", suggestion)?;
+    }
+
+    Ok(())
+}
+
+impl std::fmt::Display for ParserError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParserError::EmptyAddress => write!(f, "Empty address provided"),
+            ParserError::InvalidNumber(n) => write!(f, "Unable to parse number: {}", n),
+            ParserError::InvalidFormat => write!(f, "Invalid instruction format"),
+            ParserError::InvalidSymbol => write!(f, "Invalid symbol format"),
+            ParserError::InvalidComp(c, s) => write_mnemonic_error(f, "comp", c, s),
+            ParserError::InvalidDest(d, s) => write_mnemonic_error(f, "dest", d, s),
+            ParserError::InvalidJump(j, s) => write_mnemonic_error(f, "jump", j, s),
+            ParserError::AddressOutOfRange(a) => write!(
+                f,
+                "Constant '{}' is out of range, A-instructions hold at most {}",
+                a, MAX_ADDRESS
+            ),
+            ParserError::ExtendedLiteralDisabled(a) => write!(
+                f,
+                "Literal '{}' requires the --extended-literals flag",
+                a
+            ),
+            ParserError::DuplicateLabel(l, location) => {
+                write!(f, "Label '{}' is already defined at {}", l, location)
+            }
+            ParserError::ReservedLabel(l) => write!(
+                f,
+                "Label '{}' would redefine a predefined symbol",
+                l
+            ),
+        }
+    }
+}
+
+impl From<std::num::ParseIntError> for ParserError {
+    fn from(err: std::num::ParseIntError) -> Self {
+        ParserError::InvalidNumber(err)
+    }
+}
+
+pub struct Parser {
+    file_name: String,
+    program_counter: usize,
+    symbols: HashMap<String, usize>,
+    symbol_counter: usize,
+    extended_literals: bool,
+    symbol_file: SymbolFile,
+    label_lines: HashMap<String, String>,
+    label_origins: Vec<(String, usize)>,
+    warnings: Diagnostics,
+}
+
+impl Parser {
+    pub fn new(file_name: String, extended_literals: bool) -> Self {
+        Self {
+            file_name,
+            program_counter: 0,
+            symbols: predefined_symbols(),
+            symbol_counter: STARTING_VARIABLE_ADDRESS,
+            extended_literals,
+            symbol_file: SymbolFile::default(),
+            label_lines: HashMap::new(),
+            label_origins: Vec::new(),
+            warnings: Diagnostics::new(),
+        }
+    }
+
+    /// Warnings from the last successful [`Parser::parse`].
+    pub fn warnings(&self) -> &Diagnostics {
+        &self.warnings
+    }
+
+    pub fn symbols(&self) -> &HashMap<String, usize> {
+        &self.symbols
+    }
+
+    /// Labels and variables in definition order, and the source line of
+    /// every instruction.
+    pub fn symbol_file(&self) -> &SymbolFile {
+        &self.symbol_file
+    }
+
+    /// Each label with the main-file line that produced it, see
+    /// [`SourceLine::origin`].
+    pub fn label_origins(&self) -> &[(String, usize)] {
+        &self.label_origins
+    }
+
+    fn clean_line<'a>(&self, line: &'a str) -> &'a str {
+        line.split_once("//")
+            .map(|(before, _)| before)
+            .unwrap_or(line)
+            .trim()
+    }
+
+    fn diagnostic(&self, line: &SourceLine, text: &str, error: ParserError) -> Diagnostic {
+        let text = error.text().unwrap_or(text);
+        line.diagnostic(text, error.to_string())
+    }
+
+    /// `line 12` for the file being assembled, `file:line` for included code.
+    fn location(&self, line: &SourceLine) -> String {
+        if *line.file == self.file_name {
+            format!("line {}", line.line)
+        } else {
+            format!("{}:{}", line.file, line.line)
+        }
+    }
+
+    fn first_pass(&mut self, lines: &[SourceLine], diagnostics: &mut Diagnostics) {
+        for line in lines {
+            // Take instructions before inline comments if any
+            let current_instruction = self.clean_line(&line.text);
+
+            // Check if instruction is whitespace or comment
+            if current_instruction.is_empty() {
+                continue;
+            }
+
+            // Determine instruction type
+            let instruction_type: InstructionType = self.instruction_type(current_instruction);
+
+            // Parse labels
+            if instruction_type == InstructionType::LInstruction {
+                match self.parse_label_symbol(current_instruction, line) {
+                    Ok(label) => self.check_label_case(&label, line, diagnostics),
+                    Err(e) => diagnostics.push(self.diagnostic(line, current_instruction, e)),
+                }
+            } else {
+                self.program_counter += 1;
+            }
+        }
+    }
+
+    fn second_pass(&mut self, lines: &[SourceLine], diagnostics: &mut Diagnostics) -> Vec<u16> {
+        let mut words: Vec<u16> = Vec::new();
+
+        // Second pass
+        for line in lines {
+            let current_instruction = self.clean_line(&line.text);
+
+            if current_instruction.is_empty() {
+                continue;
+            }
+
+            // Determine instruction type and parse C and A instructions
+            let result = match self.instruction_type(current_instruction) {
+                InstructionType::LInstruction => continue,
+                InstructionType::CInstruction => self.parse_c_instruction(current_instruction),
+                InstructionType::AInstruction => self.parse_a_instruction(current_instruction),
+            };
+
+            match result {
+                Ok(bin_str) => {
+                    // Code from includes and macros maps to the line that pulled it in
+                    self.symbol_file.source_lines.push((words.len(), line.origin));
+                    words.push(u16::from_str_radix(&bin_str, 2).expect("16-bit instruction"))
+                }
+                Err(e) => diagnostics.push(self.diagnostic(line, current_instruction, e)),
+            }
+        }
+
+        words
+    }
+
+    pub fn parse(&mut self, source: &str) -> Result<Vec<u16>, Diagnostics> {
+        let lines = Preprocessor::new(&self.file_name).process(source)?;
+        let mut diagnostics = Diagnostics::new();
+
+        self.first_pass(&lines, &mut diagnostics);
+        let words = self.second_pass(&lines, &mut diagnostics);
+
+        // Labels are checked in the first pass, but errors read in file order
+        diagnostics.sort();
+
+        // Only hand back a program once every instruction assembled cleanly
+        if diagnostics.error_count() > 0 {
+            return Err(diagnostics);
+        }
+
+        self.warnings = diagnostics;
+        Ok(words)
+    }
+
+    fn parse_c_instruction(&mut self, current_instruction: &str) -> Result<String, ParserError> {
+        let comp_bin_str = Code.comp_to_binary(self.comp(current_instruction)?)?;
+        let dest_str = Code.dest_to_binary(self.dest(current_instruction).unwrap_or("null"))?;
+        let jump_str = Code.jump_to_binary(self.jump(current_instruction).unwrap_or("null"))?;
+        let bin_str = format!("111{}{}{}", comp_bin_str, dest_str, jump_str);
+
+        Ok(bin_str)
+    }
+
+    fn parse_a_instruction(&mut self, current_instruction: &str) -> Result<String, ParserError> {
+        let addr_str = self
+            .addr(current_instruction)
+            .ok_or(ParserError::EmptyAddress)?;
+
+        if addr_str.is_empty() {
+            return Err(ParserError::EmptyAddress);
+        }
+
+        if SYMBOL_RE.is_match(addr_str) {
+            return self.parse_symbol(addr_str);
+        }
+
+        // Digits are already validated, so a parse failure can only be an overflow
+        let out_of_range = |_| ParserError::AddressOutOfRange(addr_str.to_string());
+        let address: usize = if ADDRESS_RE.is_match(addr_str) {
+            addr_str.parse::<usize>().map_err(out_of_range)?
+        } else if HEX_ADDRESS_RE.is_match(addr_str) || BIN_ADDRESS_RE.is_match(addr_str) {
+            // Hex (@0x4000) and binary (@0b101) literals are an opt-in extension
+            if !self.extended_literals {
+                return Err(ParserError::ExtendedLiteralDisabled(addr_str.to_string()));
+            }
+
+            let radix: u32 = if HEX_ADDRESS_RE.is_match(addr_str) { 16 } else { 2 };
+            usize::from_str_radix(&addr_str[2..], radix).map_err(out_of_range)?
+        } else {
+            return Err(ParserError::InvalidFormat);
+        };
+
+        if address > MAX_ADDRESS {
+            return Err(ParserError::AddressOutOfRange(addr_str.to_string()));
+        }
+
+        Ok(format!("{:016b}", address))
+    }
+
+    fn parse_label_symbol(
+        &mut self,
+        current_instruction: &str,
+        line: &SourceLine,
+    ) -> Result<String, ParserError> {
+        // Remove parenthesis
+        let label_str = &current_instruction[1..current_instruction.len() - 1];
+
+        if SYMBOL_RE.is_match(label_str) {
+            // Only labels and predefined symbols exist during the first pass
+            if let Some(first_location) = self.label_lines.get(label_str) {
+                return Err(ParserError::DuplicateLabel(
+                    label_str.to_string(),
+                    first_location.clone(),
+                ));
+            }
+            if self.symbols.contains_key(label_str) {
+                return Err(ParserError::ReservedLabel(label_str.to_string()));
+            }
+
+            self.label_lines
+                .insert(label_str.to_string(), self.location(line));
+            self.symbols
+                .insert(label_str.to_string(), self.program_counter);
+            self.symbol_file
+                .labels
+                .push((label_str.to_string(), self.program_counter));
+            self.label_origins
+                .push((label_str.to_string(), line.origin));
+            return Ok(label_str.to_string());
+        }
+
+        Err(ParserError::InvalidFormat)
+    }
+
+    fn check_label_case(
+        &self,
+        label: &str,
+        line: &SourceLine,
+        diagnostics: &mut Diagnostics,
+    ) {
+        // `(loop)` next to `(LOOP)` is almost always a typo, but still legal
+        let Some(other) = self
+            .symbols
+            .keys()
+            .filter(|name| *name != label && name.eq_ignore_ascii_case(label))
+            .min()
+        else {
+            return;
+        };
+
+        let message = match self.label_lines.get(other) {
+            Some(other_location) => format!(
+                "Label '{}' differs only by case from label '{}' at {}",
+                label, other, other_location
+            ),
+            None => format!(
+                "Label '{}' differs only by case from predefined symbol '{}'",
+                label, other
+            ),
+        };
+        diagnostics.push(line.warning(label, message));
+    }
+
+    fn parse_symbol(&mut self, addr_str: &str) -> Result<String, ParserError> {
+        // If symbol is not in the symbol table, add it to symbol table as variable
+        if !self.symbols.contains_key(addr_str) {
+            self.symbols
+                .insert(addr_str.to_string(), self.symbol_counter);
+            self.symbol_file
+                .variables
+                .push((addr_str.to_string(), self.symbol_counter));
+            self.symbol_counter += 1;
+        }
+
+        let Some(symbol_val) = self.symbols.get(addr_str) else {
+            return Err(ParserError::InvalidSymbol);
+        };
+        let bin_str = format!("{:016b}", symbol_val & MAX_ADDRESS);
+
+        Ok(bin_str)
+    }
+
+    fn addr<'a>(&self, instruction: &'a str) -> Option<&'a str> {
+        Some(&instruction[1..])
+    }
+
+    fn comp<'a>(&self, instruction: &'a str) -> Result<&'a str, ParserError> {
+        // Handle DEST=COMP
+        if let Some((_, after)) = instruction.rsplit_once('=') {
+            // Handle DEST=COMP;JUMP
+            if let Some((middle, _)) = after.rsplit_once(';') {
+                return Ok(middle);
+            } else {
+                return Ok(after);
+            }
+        // Handle COMP;JUMP
+        } else if let Some((before, _)) = instruction.rsplit_once(';') {
+            return Ok(before);
+        };
+
+        Err(ParserError::InvalidFormat)
+    }
+
+    fn dest<'a>(&self, instruction: &'a str) -> Option<&'a str> {
+        let (dest_str, _) = instruction.rsplit_once('=')?;
+
+        Some(dest_str)
+    }
+
+    fn jump<'a>(&self, instruction: &'a str) -> Option<&'a str> {
+        let (_, jump_str) = instruction.rsplit_once(';')?;
+
+        Some(jump_str)
+    }
+
+    fn instruction_type(&self, line: &str) -> InstructionType {
+        if line.starts_with("@") {
+            return InstructionType::AInstruction;
+        } else if line.starts_with("(") && line.ends_with(")") {
+            return InstructionType::LInstruction;
+        }
+
+        InstructionType::CInstruction
+    }
+}
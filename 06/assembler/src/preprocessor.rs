@@ -0,0 +1,469 @@
+use hack_core::diagnostics::{Diagnostic, Diagnostics, Severity};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+static MACRO_NAME_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"^[A-Za-z_][A-Za-z_0-9]*$").unwrap());
+
+/// One line of preprocessed source, with where it came from.
+#[derive(Debug, Clone)]
+pub struct SourceLine {
+    /// File the text was read from (the main file, an include, or the file
+    /// a macro was defined in)
+    pub file: Rc<str>,
+    /// 1-based line number within `file`
+    pub line: usize,
+    /// Line of the main file that produced this line, i.e. the `.include`
+    /// or macro call it was expanded from
+    pub origin: usize,
+    pub text: String,
+    /// Set for lines produced by a macro call
+    pub expansion: Option<Rc<Expansion>>,
+}
+
+/// Where an expanded line came from. Its text has the arguments
+/// substituted, so errors are reported at the call instead.
+#[derive(Debug)]
+pub struct Expansion {
+    /// The outermost call, as written in the source
+    pub call: SourceLine,
+    /// `in macro 'NAME' at line N`, naming the body line
+    pub note: String,
+}
+
+impl SourceLine {
+    /// An error about `text` on this line, or on the macro call it was
+    /// expanded from.
+    pub fn diagnostic(&self, text: &str, message: String) -> Diagnostic {
+        match &self.expansion {
+            Some(expansion) => {
+                // Underline the argument that caused it, or else the macro name
+                let call = &expansion.call;
+                let words: Vec<(usize, &str)> = call
+                    .text
+                    .split(|c: char| c == ',' || c.is_whitespace())
+                    .scan(0, |offset, word| {
+                        let start = *offset;
+                        *offset += word.len() + 1;
+                        Some((start, word))
+                    })
+                    .filter(|(_, word)| !word.is_empty())
+                    .take_while(|(_, word)| !word.starts_with("//"))
+                    .collect();
+                let (start, text) = words
+                    .iter()
+                    .skip(1)
+                    .find(|(_, arg)| !text.is_empty() && arg.contains(text))
+                    .map(|&(start, arg)| (start + arg.find(text).unwrap_or(0), text))
+                    .or_else(|| words.first().copied())
+                    .unwrap_or((0, ""));
+
+                let message = format!("{} ({})", message, expansion.note);
+                let mut diagnostic =
+                    Diagnostic::new(&call.file, call.line, &call.text, text, message);
+                diagnostic.column = start + 1;
+                diagnostic
+            }
+            None => Diagnostic::new(&self.file, self.line, &self.text, text, message),
+        }
+    }
+
+    /// Same as [`SourceLine::diagnostic`], but reported as a warning.
+    pub fn warning(&self, text: &str, message: String) -> Diagnostic {
+        Diagnostic {
+            severity: Severity::Warning,
+            ..self.diagnostic(text, message)
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum PreprocessorError {
+    InvalidMacroName(String),
+    DuplicateMacro(String, String),
+    NestedMacro,
+    UnterminatedMacro(String),
+    UnexpectedEndm,
+    ArgumentCount(String, usize, usize),
+    RecursiveMacro(String),
+    InvalidInclude,
+    IncludeNotFound(String, std::io::Error),
+    RecursiveInclude(String),
+}
+
+impl PreprocessorError {
+    fn text(&self) -> Option<&str> {
+        match self {
+            PreprocessorError::InvalidMacroName(text)
+            | PreprocessorError::DuplicateMacro(text, _)
+            | PreprocessorError::UnterminatedMacro(text)
+            | PreprocessorError::ArgumentCount(text, _, _)
+            | PreprocessorError::RecursiveMacro(text)
+            | PreprocessorError::IncludeNotFound(text, _)
+            | PreprocessorError::RecursiveInclude(text) => Some(text),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for PreprocessorError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PreprocessorError::InvalidMacroName(name) => {
+                write!(f, "Invalid macro name '{}'", name)
+            }
+            PreprocessorError::DuplicateMacro(name, location) => {
+                write!(f, "Macro '{}' is already defined at {}", name, location)
+            }
+            PreprocessorError::NestedMacro => {
+                write!(f, "Macros can't be defined inside another macro")
+            }
+            PreprocessorError::UnterminatedMacro(name) => {
+                write!(f, "Macro '{}' is missing its .endm", name)
+            }
+            PreprocessorError::UnexpectedEndm => write!(f, ".endm without a matching .macro"),
+            PreprocessorError::ArgumentCount(name, expected, found) => write!(
+                f,
+                "Macro '{}' takes {} argument(s) but {} were given",
+                name, expected, found
+            ),
+            PreprocessorError::RecursiveMacro(name) => {
+                write!(f, "Macro '{}' expands to itself", name)
+            }
+            PreprocessorError::InvalidInclude => {
+                write!(f, "Expected a quoted file name: .include \"file.asm\"")
+            }
+            PreprocessorError::IncludeNotFound(path, e) => {
+                write!(f, "Unable to include '{}': {}", path, e)
+            }
+            PreprocessorError::RecursiveInclude(path) => {
+                write!(f, "'{}' includes itself", path)
+            }
+        }
+    }
+}
+
+struct Macro {
+    params: Vec<String>,
+    body: Vec<SourceLine>,
+    /// Labels defined in the body, renamed in every expansion
+    labels: Vec<String>,
+    location: String,
+}
+
+/// Expands `.include "file.asm"` and `.macro NAME args ... .endm`
+/// definitions ahead of the parser's first pass. Macro bodies refer to
+/// their arguments as `\arg`; a call is a line starting with the macro's
+/// name followed by its arguments. Labels defined in a macro body get a
+/// unique suffix per expansion (`LOOP` becomes `LOOP$NAME.1`).
+pub struct Preprocessor {
+    main_file: Rc<str>,
+    macros: HashMap<String, Macro>,
+    expansion_count: usize,
+    include_stack: Vec<PathBuf>,
+    macro_stack: Vec<String>,
+}
+
+fn clean_line(line: &str) -> &str {
+    line.split_once("//")
+        .map(|(before, _)| before)
+        .unwrap_or(line)
+        .trim()
+}
+
+/// The label a line defines, without parentheses.
+fn defined_label(line: &str) -> Option<&str> {
+    let code = clean_line(line);
+    code.strip_prefix('(')?.strip_suffix(')')
+}
+
+impl Preprocessor {
+    pub fn new(main_file: &str) -> Self {
+        Self {
+            main_file: Rc::from(main_file),
+            macros: HashMap::new(),
+            expansion_count: 0,
+            include_stack: Vec::new(),
+            macro_stack: Vec::new(),
+        }
+    }
+
+    fn diagnostic(&self, line: &SourceLine, text: &str, error: PreprocessorError) -> Diagnostic {
+        let text = error.text().unwrap_or(text);
+        line.diagnostic(text, error.to_string())
+    }
+
+    fn location(&self, line: &SourceLine) -> String {
+        if line.file == self.main_file {
+            format!("line {}", line.line)
+        } else {
+            format!("{}:{}", line.file, line.line)
+        }
+    }
+
+    pub fn process(&mut self, source: &str) -> Result<Vec<SourceLine>, Diagnostics> {
+        let mut output = Vec::new();
+        let mut diagnostics = Diagnostics::new();
+
+        let main_path = Path::new(&*self.main_file);
+        let key = main_path
+            .canonicalize()
+            .unwrap_or_else(|_| main_path.to_path_buf());
+        self.include_stack.push(key);
+
+        let lines = self.read_lines(self.main_file.clone(), source, None);
+        self.process_lines(lines, &mut output, &mut diagnostics);
+        self.include_stack.pop();
+
+        if !diagnostics.is_empty() {
+            return Err(diagnostics);
+        }
+
+        Ok(output)
+    }
+
+    fn read_lines(&self, file: Rc<str>, source: &str, origin: Option<usize>) -> Vec<SourceLine> {
+        source
+            .lines()
+            .enumerate()
+            .map(|(i, text)| SourceLine {
+                file: file.clone(),
+                line: i + 1,
+                origin: origin.unwrap_or(i + 1),
+                text: text.to_string(),
+                expansion: None,
+            })
+            .collect()
+    }
+
+    fn process_lines(
+        &mut self,
+        lines: Vec<SourceLine>,
+        output: &mut Vec<SourceLine>,
+        diagnostics: &mut Diagnostics,
+    ) {
+        // Macro being defined: its name, the .macro line and the definition so far
+        let mut definition: Option<(String, SourceLine, Macro)> = None;
+
+        for line in lines {
+            let code = clean_line(&line.text);
+            let mut words = code.split_whitespace();
+            let first = words.next().unwrap_or("");
+
+            if let Some((_, _, current)) = definition.as_mut() {
+                match first {
+                    ".endm" => {
+                        let (name, start, current) = definition.take().unwrap();
+                        self.define_macro(name, &start, current, diagnostics);
+                    }
+                    ".macro" => diagnostics.push(self.diagnostic(
+                        &line,
+                        code,
+                        PreprocessorError::NestedMacro,
+                    )),
+                    _ => {
+                        if let Some(label) = defined_label(&line.text) {
+                            current.labels.push(label.to_string());
+                        }
+                        current.body.push(line);
+                    }
+                }
+                continue;
+            }
+
+            match first {
+                ".macro" => {
+                    let name = words.next().unwrap_or("").to_string();
+                    if !MACRO_NAME_RE.is_match(&name) {
+                        let error = PreprocessorError::InvalidMacroName(name.clone());
+                        diagnostics.push(self.diagnostic(&line, code, error));
+                    }
+
+                    let params = words
+                        .flat_map(|word| word.split(','))
+                        .filter(|param| !param.is_empty())
+                        .map(|param| param.to_string())
+                        .collect();
+                    let current = Macro {
+                        params,
+                        body: Vec::new(),
+                        labels: Vec::new(),
+                        location: self.location(&line),
+                    };
+                    definition = Some((name, line, current));
+                }
+                ".endm" => diagnostics.push(self.diagnostic(
+                    &line,
+                    code,
+                    PreprocessorError::UnexpectedEndm,
+                )),
+                ".include" => self.include(&line, output, diagnostics),
+                _ if self.macros.contains_key(first) => {
+                    let args: Vec<String> = code[first.len()..]
+                        .split(|c: char| c == ',' || c.is_whitespace())
+                        .filter(|arg| !arg.is_empty())
+                        .map(|arg| arg.to_string())
+                        .collect();
+                    self.expand(first.to_string(), args, &line, output, diagnostics);
+                }
+                _ => output.push(line),
+            }
+        }
+
+        if let Some((name, start, _)) = definition {
+            diagnostics.push(self.diagnostic(
+                &start,
+                &name,
+                PreprocessorError::UnterminatedMacro(name.clone()),
+            ));
+        }
+    }
+
+    fn define_macro(
+        &mut self,
+        name: String,
+        start: &SourceLine,
+        definition: Macro,
+        diagnostics: &mut Diagnostics,
+    ) {
+        if let Some(existing) = self.macros.get(&name) {
+            let error = PreprocessorError::DuplicateMacro(name.clone(), existing.location.clone());
+            diagnostics.push(self.diagnostic(start, &name, error));
+            return;
+        }
+
+        self.macros.insert(name, definition);
+    }
+
+    fn include(
+        &mut self,
+        line: &SourceLine,
+        output: &mut Vec<SourceLine>,
+        diagnostics: &mut Diagnostics,
+    ) {
+        let code = clean_line(&line.text);
+        let Some(path) = code[".include".len()..]
+            .trim()
+            .strip_prefix('"')
+            .and_then(|rest| rest.strip_suffix('"'))
+        else {
+            diagnostics.push(self.diagnostic(line, code, PreprocessorError::InvalidInclude));
+            return;
+        };
+
+        // Relative to the including file
+        let full_path = Path::new(&*line.file)
+            .parent()
+            .unwrap_or(Path::new(""))
+            .join(path);
+        let key = full_path
+            .canonicalize()
+            .unwrap_or_else(|_| full_path.clone());
+        if self.include_stack.contains(&key) {
+            let error = PreprocessorError::RecursiveInclude(path.to_string());
+            diagnostics.push(self.diagnostic(line, path, error));
+            return;
+        }
+
+        let source = match fs::read_to_string(&full_path) {
+            Ok(source) => source,
+            Err(e) => {
+                let error = PreprocessorError::IncludeNotFound(path.to_string(), e);
+                diagnostics.push(self.diagnostic(line, path, error));
+                return;
+            }
+        };
+
+        let file: Rc<str> = Rc::from(full_path.to_string_lossy().as_ref());
+        let lines = self.read_lines(file, &source, Some(line.origin));
+        self.include_stack.push(key);
+        self.process_lines(lines, output, diagnostics);
+        self.include_stack.pop();
+    }
+
+    fn expand(
+        &mut self,
+        name: String,
+        args: Vec<String>,
+        call: &SourceLine,
+        output: &mut Vec<SourceLine>,
+        diagnostics: &mut Diagnostics,
+    ) {
+        if self.macro_stack.contains(&name) {
+            let error = PreprocessorError::RecursiveMacro(name.clone());
+            diagnostics.push(self.diagnostic(call, &name, error));
+            return;
+        }
+
+        let definition = &self.macros[&name];
+        if args.len() != definition.params.len() {
+            let error =
+                PreprocessorError::ArgumentCount(name.clone(), definition.params.len(), args.len());
+            diagnostics.push(self.diagnostic(call, &name, error));
+            return;
+        }
+
+        self.expansion_count += 1;
+        let suffix = format!("${}.{}", name, self.expansion_count);
+
+        // Longest parameter names first, so `\ab` isn't replaced as `\a` + "b"
+        let mut substitutions: Vec<(String, &str)> = definition
+            .params
+            .iter()
+            .zip(&args)
+            .map(|(param, arg)| (format!("\\{}", param), arg.as_str()))
+            .collect();
+        substitutions.sort_by_key(|(param, _)| std::cmp::Reverse(param.len()));
+
+        // Calls from inside another macro report at the call that started it all
+        let outermost_call = match &call.expansion {
+            Some(expansion) => expansion.call.clone(),
+            None => call.clone(),
+        };
+
+        let lines: Vec<SourceLine> = definition
+            .body
+            .iter()
+            .map(|body_line| {
+                let mut text = body_line.text.clone();
+                for (param, arg) in &substitutions {
+                    text = text.replace(param, arg);
+                }
+
+                let code = clean_line(&text).to_string();
+                let local = |symbol: &str| definition.labels.iter().any(|label| label == symbol);
+                let renamed = if let Some(label) = code
+                    .strip_prefix('(')
+                    .and_then(|rest| rest.strip_suffix(')'))
+                {
+                    local(label).then(|| format!("({}{})", label, suffix))
+                } else if let Some(symbol) = code.strip_prefix('@') {
+                    local(symbol).then(|| format!("@{}{}", symbol, suffix))
+                } else {
+                    None
+                };
+                if let Some(renamed) = renamed {
+                    text = text.replacen(&code, &renamed, 1);
+                }
+
+                SourceLine {
+                    file: body_line.file.clone(),
+                    line: body_line.line,
+                    origin: call.origin,
+                    text,
+                    expansion: Some(Rc::new(Expansion {
+                        call: outermost_call.clone(),
+                        note: format!("in macro '{}' at {}", name, self.location(body_line)),
+                    })),
+                }
+            })
+            .collect();
+
+        self.macro_stack.push(name);
+        self.process_lines(lines, output, diagnostics);
+        self.macro_stack.pop();
+    }
+}
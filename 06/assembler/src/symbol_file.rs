@@ -0,0 +1,113 @@
+use std::fmt;
+
+/// Labels (ROM addresses) and variables (RAM addresses) of an assembled
+/// program, one `label NAME ADDRESS` or `variable NAME ADDRESS` per line,
+/// plus `line ADDRESS LINE` entries mapping ROM addresses back to `.asm`
+/// source lines. `//` comments and blank lines are ignored.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SymbolFile {
+    pub labels: Vec<(String, usize)>,
+    pub variables: Vec<(String, usize)>,
+    /// (ROM address, 1-based source line)
+    pub source_lines: Vec<(usize, usize)>,
+}
+
+#[derive(Debug)]
+pub struct SymbolFileError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for SymbolFileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl SymbolFile {
+    pub fn parse(source: &str) -> Result<Self, SymbolFileError> {
+        let mut symbols = SymbolFile::default();
+
+        for (i, line) in source.lines().enumerate() {
+            let line = line
+                .split_once("//")
+                .map(|(before, _)| before)
+                .unwrap_or(line);
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let error = |message: String| SymbolFileError {
+                line: i + 1,
+                message,
+            };
+
+            let (kind, name, address) = match fields[..] {
+                [] => continue,
+                [kind, name, address] => (kind, name, address),
+                _ => {
+                    return Err(error(
+                        "expected '<label|variable> <name> <address>' or 'line <address> <line>'".to_string(),
+                    ));
+                }
+            };
+            let address = address
+                .parse::<usize>()
+                .map_err(|_| error(format!("invalid address '{}'", address)))?;
+
+            match kind {
+                "label" => symbols.labels.push((name.to_string(), address)),
+                "variable" => symbols.variables.push((name.to_string(), address)),
+                "line" => {
+                    let rom_address = name
+                        .parse::<usize>()
+                        .map_err(|_| error(format!("invalid address '{}'", name)))?;
+                    symbols.source_lines.push((rom_address, address));
+                }
+                _ => return Err(error(format!("unknown symbol kind '{}'", kind))),
+            }
+        }
+
+        Ok(symbols)
+    }
+
+    pub fn label_at(&self, address: usize) -> Option<&str> {
+        self.labels
+            .iter()
+            .find(|(_, value)| *value == address)
+            .map(|(name, _)| name.as_str())
+    }
+
+    pub fn variable_at(&self, address: usize) -> Option<&str> {
+        self.variables
+            .iter()
+            .find(|(_, value)| *value == address)
+            .map(|(name, _)| name.as_str())
+    }
+
+    /// Source line the instruction at a ROM address was assembled from.
+    pub fn source_line(&self, address: usize) -> Option<usize> {
+        self.source_lines
+            .iter()
+            .find(|(value, _)| *value == address)
+            .map(|(_, line)| *line)
+    }
+}
+
+impl fmt::Display for SymbolFile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "// Labels (ROM addresses)")?;
+        for (name, address) in &self.labels {
+            writeln!(f, "label {} {}", name, address)?;
+        }
+
+        writeln!(f, "// Variables (RAM addresses)")?;
+        for (name, address) in &self.variables {
+            writeln!(f, "variable {} {}", name, address)?;
+        }
+
+        writeln!(f, "// ROM address to source line")?;
+        for (address, line) in &self.source_lines {
+            writeln!(f, "line {} {}", address, line)?;
+        }
+
+        Ok(())
+    }
+}
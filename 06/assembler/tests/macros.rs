@@ -0,0 +1,68 @@
+use hack_asm::assemble;
+
+/// The (line, column, message) of each error from assembling `source`.
+fn errors(source: &str) -> Vec<(usize, usize, String)> {
+    let diagnostics = assemble(source).expect_err("source has errors");
+
+    diagnostics
+        .iter()
+        .map(|diagnostic| {
+            (
+                diagnostic.line,
+                diagnostic.column,
+                diagnostic.message.clone(),
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn errors_in_an_expansion_point_at_the_call() {
+    let source = "\
+.macro SETD val
+@\\val
+D=A
+D=Q
+.endm
+SETD 40000
+.macro TWICE v
+SETD \\v
+.endm
+TWICE 5
+";
+
+    assert_eq!(
+        errors(source),
+        vec![
+            (
+                6,
+                6,
+                "Constant '40000' is out of range, A-instructions hold at most 32767 \
+                 (in macro 'SETD' at line 2)"
+                    .to_string()
+            ),
+            (
+                6,
+                1,
+                "Unknown comp mnemonic 'Q', did you mean '0'? (in macro 'SETD' at line 4)"
+                    .to_string()
+            ),
+            (
+                10,
+                1,
+                "Unknown comp mnemonic 'Q', did you mean '0'? (in macro 'SETD' at line 4)"
+                    .to_string()
+            ),
+        ]
+    );
+}
+
+#[test]
+fn expansion_errors_echo_the_call_as_written() {
+    let source = ".macro SETD val\n@\\val\n.endm\nSETD 40000\n";
+    let diagnostics = assemble(source).expect_err("source has errors");
+    let diagnostic = diagnostics.iter().next().expect("one error");
+
+    assert_eq!(diagnostic.source_line, "SETD 40000");
+    assert_eq!(diagnostic.text, "40000");
+}
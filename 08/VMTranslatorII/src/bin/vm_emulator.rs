@@ -0,0 +1,103 @@
+use std::env;
+use std::path::Path;
+use std::process;
+use vm_translator::emulator::{MEMORY_SIZE, RunOutcome, SP, VmEmulator};
+use vm_translator::parser::{self, collect_input_filepaths};
+
+const DEFAULT_MAX_STEPS: u64 = 1_000_000;
+const STACK_BASE: usize = 256;
+
+fn main() {
+    let (flags, args): (Vec<String>, Vec<String>) =
+        env::args().skip(1).partition(|arg| arg.starts_with("--"));
+    let (pokes, args): (Vec<String>, Vec<String>) =
+        args.into_iter().partition(|arg| arg.contains('='));
+    if args.is_empty() || args.len() > 2 {
+        println!(
+            "Usage: vm_emulator <input file or directory> [max steps] [--bootstrap|--no-bootstrap] [address=value ...]"
+        );
+        return;
+    }
+
+    let input_path = Path::new(&args[0]);
+
+    // Same default as the translator: directories start from Sys.init
+    let mut bootstrap: bool = input_path.is_dir();
+    for flag in &flags {
+        match flag.as_str() {
+            "--bootstrap" => bootstrap = true,
+            "--no-bootstrap" => bootstrap = false,
+            _ => {
+                println!("Unknown option: {}", flag);
+                return;
+            }
+        }
+    }
+
+    let max_steps: u64 = match args.get(1) {
+        Some(steps) => steps.parse().expect("max steps"),
+        None => DEFAULT_MAX_STEPS,
+    };
+
+    let input_filepaths = collect_input_filepaths(input_path).expect("input files");
+    let mut parser = parser::Parser::without_writer(input_filepaths);
+    let commands = parser.commands().unwrap_or_else(|e| {
+        eprintln!("Error reading {}: {}", args[0], e);
+        process::exit(1);
+    });
+
+    let diagnostics = parser.diagnostics();
+    if !diagnostics.is_empty() {
+        eprintln!("{}", diagnostics);
+        eprintln!(
+            "error: could not load '{}' due to {} previous error(s)",
+            args[0],
+            diagnostics.len()
+        );
+        process::exit(1);
+    }
+
+    let mut emulator = VmEmulator::new(commands).unwrap_or_else(|e| {
+        eprintln!("error: {}", e);
+        process::exit(1);
+    });
+
+    // RAM is set up before bootstrapping, like a test script's set commands
+    for poke in &pokes {
+        let (address, value) = poke.split_once('=').expect("address=value");
+        let address: usize = address.parse().expect("address");
+        let value: i32 = value.parse().expect("value");
+        emulator.poke(address, value as u16);
+    }
+
+    let outcome = if bootstrap {
+        emulator.bootstrap().and_then(|_| emulator.run(max_steps))
+    } else {
+        emulator.run(max_steps)
+    };
+
+    match outcome {
+        Ok(RunOutcome::Halted { steps }) => println!("Halted after {} steps", steps),
+        Ok(RunOutcome::StepLimit { steps }) => println!("Stopped after {} steps", steps),
+        Err(e) => {
+            eprintln!("error: {}", e);
+            process::exit(1);
+        }
+    }
+
+    for (i, value) in emulator.memory()[..16].iter().enumerate() {
+        println!("RAM[{}]={}", i, *value as i16);
+    }
+
+    // SP is a full 16-bit word: popping an empty stack wraps it past RAM
+    let sp = emulator.peek(SP) as usize;
+    if sp >= MEMORY_SIZE {
+        println!("Stack: SP {} is outside RAM", sp as i16);
+    } else if sp > STACK_BASE {
+        let stack: Vec<String> = emulator.memory()[STACK_BASE..sp]
+            .iter()
+            .map(|value| (*value as i16).to_string())
+            .collect();
+        println!("Stack: {}", stack.join(" "));
+    }
+}
@@ -1,7 +1,9 @@
+use crate::optimizer;
 use crate::parser;
 use std::collections::HashMap;
 use std::fs::File;
 use std::io::Write;
+use std::path::Path;
 
 enum ArithmeticTranslation {
     Add,
@@ -116,6 +118,147 @@ impl LogicalTranslation {
         )
     }
 
+    /// Ordered comparison that can't overflow: `x - y` only decides when x
+    /// and y have the same sign. Otherwise D is set to -1 (x < y) or 1
+    /// (x > y) from the signs alone. `label` names the branch targets.
+    fn generate_ordered_compare_asm(&self, label: impl Fn(&str) -> String, jump: &str) -> String {
+        format!(
+            // SP--
+            // R13 = y, D = x
+            // if x < 0 and y >= 0 then D = -1
+            // if x >= 0 and y < 0 then D = 1
+            // otherwise D = x - y
+            // RAM[SP - 1] = if D <jump> then -1 else 0
+            "@SP
+            AM=M-1
+            D=M
+            @R13
+            M=D
+            @SP
+            A=M-1
+            D=M
+            @{0}
+            D;JLT
+            @R13
+            D=M
+            @{1}
+            D;JGE
+            D=1
+            @{2}
+            0;JMP
+            ({0})
+            @R13
+            D=M
+            @{1}
+            D;JLT
+            D=-1
+            @{2}
+            0;JMP
+            ({1})
+            @R13
+            D=M
+            @SP
+            A=M-1
+            D=M-D
+            ({2})
+            @SP
+            A=M-1
+            M=-1
+            @{3}
+            D;{4}
+            @SP
+            A=M-1
+            M=0
+            ({3})",
+            label("X_NEGATIVE"),
+            label("SAME_SIGN"),
+            label("SET"),
+            label("END"),
+            jump
+        )
+    }
+
+    fn keyword_and_jump(&self) -> (&'static str, &'static str) {
+        match self {
+            LogicalTranslation::Equal => ("EQUAL", "JEQ"),
+            LogicalTranslation::LessThan => ("LT", "JLT"),
+            LogicalTranslation::GreaterThan => ("GT", "JGT"),
+        }
+    }
+
+    fn shared_label(&self) -> String {
+        format!("$VM.{}", self.keyword_and_jump().0)
+    }
+
+    fn shared_call(&self, cnt: usize) -> String {
+        let (keyword, _) = self.keyword_and_jump();
+
+        dedent(format!(
+            // D = return address
+            // goto shared routine
+            // (return address)
+            "@{0}_RET_{1}
+            D=A
+            @{2}
+            0;JMP
+            ({0}_RET_{1})",
+            keyword,
+            cnt,
+            self.shared_label()
+        ))
+    }
+
+    fn shared_routine(&self) -> String {
+        let (_, jump) = self.keyword_and_jump();
+
+        if !matches!(self, LogicalTranslation::Equal) {
+            let label = self.shared_label();
+            let compare_asm =
+                self.generate_ordered_compare_asm(|name| format!("{}.{}", label, name), jump);
+
+            return dedent(format!(
+                // R15 = return address
+                // <ordered compare>
+                // goto R15
+                "({0})
+                @R15
+                M=D
+                {1}
+                @R15
+                A=M
+                0;JMP",
+                label, compare_asm
+            ));
+        }
+
+        dedent(format!(
+            // R15 = return address
+            // SP--
+            // RAM[SP - 1] = if RAM[SP - 1] - RAM[SP] <jump> then -1 else 0
+            // goto R15
+            "({0})
+            @R15
+            M=D
+            @SP
+            AM=M-1
+            D=M
+            A=A-1
+            D=M-D
+            M=-1
+            @{0}.TRUE
+            D;{1}
+            @SP
+            A=M-1
+            M=0
+            ({0}.TRUE)
+            @R15
+            A=M
+            0;JMP",
+            self.shared_label(),
+            jump
+        ))
+    }
+
     fn value(&self, command_counts: &HashMap<String, usize>) -> String {
         match *self {
             LogicalTranslation::Equal => {
@@ -126,13 +269,15 @@ impl LogicalTranslation {
             }
             LogicalTranslation::LessThan => {
                 let lt_cnt: usize = *command_counts.get("lt").expect("lt key");
-                let lt_asm: String = self.generate_bool_compare_asm("LT", "JLT", lt_cnt);
+                let lt_asm: String = self
+                    .generate_ordered_compare_asm(|name| format!("LT_{}_{}", name, lt_cnt), "JLT");
 
                 dedent(lt_asm)
             }
             LogicalTranslation::GreaterThan => {
                 let gt_cnt: usize = *command_counts.get("gt").expect("gt key");
-                let gt_asm: String = self.generate_bool_compare_asm("GT", "JGT", gt_cnt);
+                let gt_asm: String = self
+                    .generate_ordered_compare_asm(|name| format!("GT_{}_{}", name, gt_cnt), "JGT");
 
                 dedent(gt_asm)
             }
@@ -173,9 +318,239 @@ impl Branching {
     }
 }
 
+enum FunctionCommand {
+    Function(String, usize),
+    Call(String, usize, String),
+    Return,
+    SharedCall(String, usize, String),
+    SharedReturn,
+}
+
+const SHARED_CALL_LABEL: &str = "$VM.CALL";
+const SHARED_RETURN_LABEL: &str = "$VM.RETURN";
+
+impl FunctionCommand {
+    fn push_pointer(symbol: &str) -> String {
+        format!(
+            // RAM[SP] = <symbol>
+            // SP++
+            "@{}
+            D=M
+            @SP
+            A=M
+            M=D
+            @SP
+            M=M+1",
+            symbol
+        )
+    }
+
+    fn restore_pointer(symbol: &str) -> String {
+        format!(
+            // frame--
+            // <symbol> = RAM[frame]
+            "@R13
+            AM=M-1
+            D=M
+            @{}
+            M=D",
+            symbol
+        )
+    }
+
+    /// Pushes the return address in D and the caller's frame, then jumps to
+    /// the function in R14 with ARG = SP - R13.
+    fn shared_call_routine() -> String {
+        let saved_pointers: Vec<String> = ["LCL", "ARG", "THIS", "THAT"]
+            .iter()
+            .map(|symbol| {
+                format!(
+                    "@{}
+                    D=M
+                    @SP
+                    AM=M+1
+                    A=A-1
+                    M=D",
+                    symbol
+                )
+            })
+            .collect();
+
+        dedent(format!(
+            // push D (return address)
+            // push LCL, ARG, THIS, THAT
+            // LCL = SP
+            // ARG = SP - R13 (n_args + 5)
+            // goto R14
+            "({0})
+            @SP
+            AM=M+1
+            A=A-1
+            M=D
+            {1}
+            @SP
+            D=M
+            @LCL
+            M=D
+            @R13
+            D=D-M
+            @ARG
+            M=D
+            @R14
+            A=M
+            0;JMP",
+            SHARED_CALL_LABEL,
+            saved_pointers.join("\n")
+        ))
+    }
+
+    fn shared_return_routine() -> String {
+        format!("({})\n{}", SHARED_RETURN_LABEL, FunctionCommand::Return.value())
+    }
+
+    fn value(&self) -> String {
+        match self {
+            FunctionCommand::SharedCall(name, n_args, return_label) => dedent(format!(
+                // R13 = n_args + 5
+                // R14 = function address
+                // D = return address
+                // goto shared call routine
+                // (return address)
+                "@{1}
+                D=A
+                @R13
+                M=D
+                @{0}
+                D=A
+                @R14
+                M=D
+                @{2}
+                D=A
+                @{3}
+                0;JMP
+                ({2})",
+                name,
+                n_args + 5,
+                return_label,
+                SHARED_CALL_LABEL
+            )),
+            FunctionCommand::SharedReturn => dedent(format!(
+                "@{}
+                0;JMP",
+                SHARED_RETURN_LABEL
+            )),
+            FunctionCommand::Function(name, n_vars) => {
+                // (name)
+                // repeat n_vars: RAM[SP] = 0, SP++
+                let mut asm: Vec<String> = vec![format!("({})", name)];
+
+                for _ in 0..*n_vars {
+                    asm.push(dedent(
+                        "@SP
+                        A=M
+                        M=0
+                        @SP
+                        M=M+1"
+                            .to_string(),
+                    ));
+                }
+
+                asm.join("\n")
+            }
+            FunctionCommand::Call(name, n_args, return_label) => {
+                let saved_pointers: Vec<String> = ["LCL", "ARG", "THIS", "THAT"]
+                    .iter()
+                    .map(|symbol| FunctionCommand::push_pointer(symbol))
+                    .collect();
+
+                dedent(format!(
+                    // push return address
+                    // push LCL, ARG, THIS, THAT
+                    // ARG = SP - 5 - n_args
+                    // LCL = SP
+                    // goto name
+                    // (return address)
+                    "@{2}
+                    D=A
+                    @SP
+                    A=M
+                    M=D
+                    @SP
+                    M=M+1
+                    {3}
+                    @SP
+                    D=M
+                    @{1}
+                    D=D-A
+                    @ARG
+                    M=D
+                    @SP
+                    D=M
+                    @LCL
+                    M=D
+                    @{0}
+                    0;JMP
+                    ({2})",
+                    name,
+                    n_args + 5,
+                    return_label,
+                    saved_pointers.join("\n")
+                ))
+            }
+            FunctionCommand::Return => {
+                let restored_pointers: Vec<String> = ["THAT", "THIS", "ARG", "LCL"]
+                    .iter()
+                    .map(|symbol| FunctionCommand::restore_pointer(symbol))
+                    .collect();
+
+                dedent(format!(
+                    // frame = LCL (R13)
+                    // return address = RAM[frame - 5] (R14)
+                    // RAM[ARG] = pop()
+                    // SP = ARG + 1
+                    // restore THAT, THIS, ARG, LCL from the frame
+                    // goto return address
+                    "@LCL
+                    D=M
+                    @R13
+                    M=D
+                    @5
+                    A=D-A
+                    D=M
+                    @R14
+                    M=D
+                    @SP
+                    M=M-1
+                    A=M
+                    D=M
+                    @ARG
+                    A=M
+                    M=D
+                    @ARG
+                    D=M+1
+                    @SP
+                    M=D
+                    {}
+                    @R14
+                    A=M
+                    0;JMP",
+                    restored_pointers.join("\n")
+                ))
+            }
+        }
+    }
+}
+
 pub struct CodeWriter {
     output_file: File,
     command_counts: HashMap<String, usize>,
+    file_name: String,
+    current_function: String,
+    return_count: usize,
+    optimize: bool,
+    buffer: Vec<String>,
+    shared_subroutines: bool,
+    used_subroutines: Vec<String>,
 }
 
 impl CodeWriter {
@@ -188,23 +563,117 @@ impl CodeWriter {
                 ("lt".to_string(), 0),
                 ("gt".to_string(), 0),
             ]),
+            file_name: String::new(),
+            current_function: String::new(),
+            return_count: 0,
+            optimize: false,
+            buffer: Vec::new(),
+            shared_subroutines: false,
+            used_subroutines: Vec::new(),
+        }
+    }
+
+    /// Translates eq/lt/gt, call and return into jumps to one shared copy
+    /// of each routine, emitted after the program by [`CodeWriter::close`].
+    pub fn set_shared_subroutines(&mut self, shared_subroutines: bool) {
+        self.shared_subroutines = shared_subroutines;
+    }
+
+    fn use_subroutine(&mut self, routine: String) {
+        if !self.used_subroutines.contains(&routine) {
+            self.used_subroutines.push(routine);
         }
     }
 
+    /// Runs the peephole optimizer over the whole program before it is
+    /// written; the output is then only written by [`CodeWriter::close`].
+    pub fn set_optimize(&mut self, optimize: bool) {
+        self.optimize = optimize;
+    }
+
+    fn emit(&mut self, asm: &str) -> Result<(), std::io::Error> {
+        if self.optimize {
+            self.buffer.extend(asm.lines().map(str::to_string));
+            return Ok(());
+        }
+
+        writeln!(self.output_file, "{}", asm)
+    }
+
+    /// Writes the shared subroutines, if any, and anything held back for
+    /// optimization.
+    pub fn close(&mut self) -> Result<(), std::io::Error> {
+        if !self.used_subroutines.is_empty() {
+            // Programs without bootstrap code run off their end; stop them
+            // before they reach the routines
+            let halt_asm: String = dedent(
+                "($VM.HALT)
+                @$VM.HALT
+                0;JMP"
+                    .to_string(),
+            );
+            self.emit(&halt_asm)?;
+
+            for routine in std::mem::take(&mut self.used_subroutines) {
+                self.emit(&routine)?;
+            }
+        }
+
+        if self.optimize {
+            let lines = optimizer::optimize(std::mem::take(&mut self.buffer));
+            for line in lines {
+                writeln!(self.output_file, "{}", line)?;
+            }
+        }
+
+        self.output_file.flush()
+    }
+
+    pub fn set_file_name(&mut self, input_filepath: &str) {
+        // Static variables are namespaced by the input file's stem, e.g. Class1.vm -> Class1.i
+        self.file_name = Path::new(input_filepath)
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().to_string())
+            .unwrap_or_default();
+        self.current_function = String::new();
+    }
+
+    pub fn write_init(&mut self) -> Result<(), std::io::Error> {
+        // SP = 256
+        // call Sys.init 0
+        let init_asm: String = dedent(
+            "@256
+            D=A
+            @SP
+            M=D"
+            .to_string(),
+        );
+        self.emit(&init_asm)?;
+
+        self.current_function = "Bootstrap".to_string();
+        self.write_call("Sys.init".to_string(), "0".to_string())
+    }
+
     pub fn write_arithmetic_or_logical(&mut self, arg1: String) -> Result<(), std::io::Error> {
         if arg1 == "eq" || arg1 == "lt" || arg1 == "gt" {
             let cnt: usize = *self
                 .command_counts
                 .get(&arg1)
-                .expect(format!("{} key", arg1).as_str());
-            let asm_string: String = match arg1.as_str() {
-                "eq" => LogicalTranslation::Equal.value(&self.command_counts),
-                "lt" => LogicalTranslation::LessThan.value(&self.command_counts),
-                "gt" => LogicalTranslation::GreaterThan.value(&self.command_counts),
+                .unwrap_or_else(|| panic!("{} key", arg1));
+            let translation = match arg1.as_str() {
+                "eq" => LogicalTranslation::Equal,
+                "lt" => LogicalTranslation::LessThan,
+                "gt" => LogicalTranslation::GreaterThan,
                 _ => unreachable!(),
             };
+            let asm_string: String = if self.shared_subroutines {
+                self.use_subroutine(translation.shared_routine());
+                translation.shared_call(cnt)
+            } else {
+                translation.value(&self.command_counts)
+            };
 
-            writeln!(self.output_file, "{}", asm_string)?;
+            self.emit(&asm_string)?;
             self.command_counts.insert(arg1.to_string(), cnt + 1);
         } else {
             let asm_string: String = match arg1.as_str() {
@@ -214,22 +683,23 @@ impl CodeWriter {
                 "and" => ArithmeticTranslation::And.value(),
                 "or" => ArithmeticTranslation::Or.value(),
                 "not" => ArithmeticTranslation::Not.value(),
-                _ => todo!(),
+                _ => unreachable!(),
             };
 
-            writeln!(self.output_file, "{}", asm_string)?;
+            self.emit(&asm_string)?;
         }
 
         Ok(())
     }
 
     fn get_address_symbol(&self, arg1: String, arg2: &str) -> String {
+        let static_symbol: String = format!("{}.{}", self.file_name, arg2);
         let address_symbol: &str = match arg1.as_str() {
             "local" => "LCL",
             "this" => "THIS",
             "that" => "THAT",
             "argument" => "ARG",
-            "static" => arg2,
+            "static" => &static_symbol,
             "pointer" => {
                 if arg2 == "0" {
                     "THIS"
@@ -271,11 +741,8 @@ impl CodeWriter {
                 ),
                 "static" => format!(
                     "@{}
-                    D=A
-                    @16
-                    A=D+A
                     D=M",
-                    arg2.as_str()
+                    self.get_address_symbol(arg1, &arg2)
                 ),
                 "pointer" => format!(
                     "@{}
@@ -324,13 +791,8 @@ impl CodeWriter {
             let address_asm = match arg1.as_str() {
                 "constant" => arg2.as_str(),
                 "temp" => &self.get_address_symbol(arg1, &arg2),
-                "static" => &format!(
-                    "{}",
-                    (arg2.parse::<usize>().expect("static") + 16)
-                        .to_string()
-                        .as_str()
-                ),
-                "pointer" => &format!("{}", this_or_that),
+                "static" => &self.get_address_symbol(arg1, &arg2),
+                "pointer" => this_or_that,
                 _ => unreachable!(),
             };
 
@@ -375,21 +837,30 @@ impl CodeWriter {
     ) -> Result<(), std::io::Error> {
         if instruction_type == parser::InstructionType::Push {
             let push_asm: String = dedent(self.get_push_asm(arg1, arg2));
-            writeln!(self.output_file, "{}", push_asm)?;
+            self.emit(&push_asm)?;
         } else if instruction_type == parser::InstructionType::Pop {
             let pop_asm: String = dedent(self.get_pop_asm(arg1, arg2));
-            writeln!(self.output_file, "{}", pop_asm)?;
+            self.emit(&pop_asm)?;
         }
 
         Ok(())
     }
 
+    fn function_label(&self, label: &str) -> String {
+        // Labels are scoped to their enclosing function as functionName$label
+        if self.current_function.is_empty() {
+            label.to_string()
+        } else {
+            format!("{}${}", self.current_function, label)
+        }
+    }
+
     pub fn write_label(
         &mut self,
         arg1: String
     ) -> Result<(), std::io::Error> {
-        let label_asm: String = Branching::Label(arg1).value();
-        writeln!(self.output_file, "{}", label_asm)?;
+        let label_asm: String = Branching::Label(self.function_label(&arg1)).value();
+        self.emit(&label_asm)?;
 
         Ok(())
     }
@@ -398,8 +869,8 @@ impl CodeWriter {
         &mut self,
         arg1: String
     ) -> Result<(), std::io::Error> {
-        let label_asm: String = Branching::Goto(arg1).value();
-        writeln!(self.output_file, "{}", label_asm)?;
+        let label_asm: String = Branching::Goto(self.function_label(&arg1)).value();
+        self.emit(&label_asm)?;
 
         Ok(())
     }
@@ -408,8 +879,44 @@ impl CodeWriter {
         &mut self,
         arg1: String
     ) -> Result<(), std::io::Error> {
-        let label_asm: String = Branching::IfGoto(arg1).value();
-        writeln!(self.output_file, "{}", label_asm)?;
+        let label_asm: String = Branching::IfGoto(self.function_label(&arg1)).value();
+        self.emit(&label_asm)?;
+
+        Ok(())
+    }
+
+    pub fn write_function(&mut self, arg1: String, arg2: String) -> Result<(), std::io::Error> {
+        let n_vars: usize = arg2.parse::<usize>().expect("function nVars");
+        let function_asm: String = FunctionCommand::Function(arg1.clone(), n_vars).value();
+        self.emit(&function_asm)?;
+        self.current_function = arg1;
+
+        Ok(())
+    }
+
+    pub fn write_call(&mut self, arg1: String, arg2: String) -> Result<(), std::io::Error> {
+        let n_args: usize = arg2.parse::<usize>().expect("call nArgs");
+        let return_label: String = format!("{}$ret.{}", self.current_function, self.return_count);
+        let call_asm: String = if self.shared_subroutines {
+            self.use_subroutine(FunctionCommand::shared_call_routine());
+            FunctionCommand::SharedCall(arg1, n_args, return_label).value()
+        } else {
+            FunctionCommand::Call(arg1, n_args, return_label).value()
+        };
+        self.emit(&call_asm)?;
+        self.return_count += 1;
+
+        Ok(())
+    }
+
+    pub fn write_return(&mut self) -> Result<(), std::io::Error> {
+        let return_asm: String = if self.shared_subroutines {
+            self.use_subroutine(FunctionCommand::shared_return_routine());
+            FunctionCommand::SharedReturn.value()
+        } else {
+            FunctionCommand::Return.value()
+        };
+        self.emit(&return_asm)?;
 
         Ok(())
     }
@@ -0,0 +1,385 @@
+use crate::parser::{Command, InstructionType};
+use hack_core::symbols::STARTING_VARIABLE_ADDRESS;
+use std::collections::HashMap;
+use std::fmt;
+use std::path::Path;
+
+/// The 15-bit data address space: RAM, screen map and keyboard.
+pub const MEMORY_SIZE: usize = 0x8000;
+
+pub const SP: usize = 0;
+pub const LCL: usize = 1;
+pub const ARG: usize = 2;
+pub const THIS: usize = 3;
+pub const THAT: usize = 4;
+pub const TEMP: usize = 5;
+
+const TRUE: u16 = 0xFFFF;
+const FALSE: u16 = 0;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum VmError {
+    UndefinedFunction(String, String),
+    UndefinedLabel(String, String),
+    DuplicateFunction(String),
+}
+
+impl fmt::Display for VmError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VmError::UndefinedFunction(name, location) => {
+                write!(f, "{}: call to undefined function '{}'", location, name)
+            }
+            VmError::UndefinedLabel(label, location) => {
+                write!(f, "{}: jump to undefined label '{}'", location, label)
+            }
+            VmError::DuplicateFunction(name) => {
+                write!(f, "Function '{}' is defined more than once", name)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RunOutcome {
+    /// The program ran past its last command or reached a `goto` loop that
+    /// can never exit
+    Halted { steps: u64 },
+    /// The step budget ran out first
+    StepLimit { steps: u64 },
+}
+
+/// Executes VM commands directly on the memory layout `CodeWriter` targets:
+/// SP, LCL, ARG, THIS and THAT in RAM[0..5], temp in RAM[5..13] and statics
+/// from RAM[16] in the order the assembler would allocate them. Each
+/// [`VmEmulator::step`] runs one command.
+///
+/// Return addresses saved in call frames are command indices rather than
+/// ROM addresses; everything else in memory matches the translated program.
+pub struct VmEmulator {
+    commands: Vec<Command>,
+    /// The command index each goto/if-goto jumps to
+    targets: Vec<Option<usize>>,
+    functions: HashMap<String, usize>,
+    statics: HashMap<String, usize>,
+    memory: Vec<u16>,
+    pc: usize,
+    steps: u64,
+}
+
+/// The static symbol `CodeWriter` emits for `static index` in `file`.
+fn static_symbol(file: &str, index: &str) -> String {
+    let stem = Path::new(file)
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_default();
+
+    format!("{}.{}", stem, index)
+}
+
+impl VmEmulator {
+    pub fn new(commands: Vec<Command>) -> Result<Self, VmError> {
+        let mut labels: HashMap<String, usize> = HashMap::new();
+        let mut jumps: Vec<(usize, String)> = Vec::new();
+        let mut functions: HashMap<String, usize> = HashMap::new();
+        let mut current_file: &str = "";
+        let mut current_function: &str = "";
+
+        for (i, command) in commands.iter().enumerate() {
+            if command.file != current_file {
+                current_file = &command.file;
+                current_function = "";
+            }
+
+            // Labels are scoped the same way CodeWriter::function_label does
+            let scoped = |label: &str| {
+                if current_function.is_empty() {
+                    label.to_string()
+                } else {
+                    format!("{}${}", current_function, label)
+                }
+            };
+
+            match command.instruction_type {
+                InstructionType::Function => {
+                    if functions.insert(command.arg1.clone(), i).is_some() {
+                        return Err(VmError::DuplicateFunction(command.arg1.clone()));
+                    }
+                    current_function = &command.arg1;
+                }
+                InstructionType::Label => {
+                    labels.insert(scoped(&command.arg1), i);
+                }
+                InstructionType::Goto | InstructionType::IfGoto => {
+                    jumps.push((i, scoped(&command.arg1)));
+                }
+                _ => {}
+            }
+        }
+
+        let mut targets: Vec<Option<usize>> = vec![None; commands.len()];
+        for (i, label) in jumps {
+            let Some(&target) = labels.get(&label) else {
+                let location = format!("{}:{}", commands[i].file, commands[i].line);
+                return Err(VmError::UndefinedLabel(label, location));
+            };
+            targets[i] = Some(target);
+        }
+
+        // The assembler allocates variables by first appearance, which is
+        // command order; calls to functions that are never defined leave an
+        // `@name` behind that takes a slot too
+        let mut statics: HashMap<String, usize> = HashMap::new();
+        for command in &commands {
+            let symbol = match command.instruction_type {
+                InstructionType::Push | InstructionType::Pop if command.arg1 == "static" => {
+                    static_symbol(&command.file, &command.arg2)
+                }
+                InstructionType::Call if !functions.contains_key(&command.arg1) => {
+                    command.arg1.clone()
+                }
+                _ => continue,
+            };
+
+            let next_address = STARTING_VARIABLE_ADDRESS + statics.len();
+            statics.entry(symbol).or_insert(next_address);
+        }
+
+        Ok(Self {
+            commands,
+            targets,
+            functions,
+            statics,
+            memory: vec![0; MEMORY_SIZE],
+            pc: 0,
+            steps: 0,
+        })
+    }
+
+    /// Runs the bootstrap code `CodeWriter::write_init` emits: SP = 256 and
+    /// `call Sys.init 0`, returning to the first command.
+    pub fn bootstrap(&mut self) -> Result<(), VmError> {
+        self.memory[SP] = 256;
+        self.pc = 0;
+        self.call("Sys.init", 0, "Bootstrap")
+    }
+
+    pub fn commands(&self) -> &[Command] {
+        &self.commands
+    }
+
+    pub fn pc(&self) -> usize {
+        self.pc
+    }
+
+    pub fn set_pc(&mut self, pc: usize) {
+        self.pc = pc;
+    }
+
+    /// The index of the `function name` command, if the program defines it.
+    pub fn function_index(&self, name: &str) -> Option<usize> {
+        self.functions.get(name).copied()
+    }
+
+    pub fn steps(&self) -> u64 {
+        self.steps
+    }
+
+    pub fn memory(&self) -> &[u16] {
+        &self.memory
+    }
+
+    pub fn peek(&self, address: usize) -> u16 {
+        self.memory[address % MEMORY_SIZE]
+    }
+
+    pub fn poke(&mut self, address: usize, value: u16) {
+        self.memory[address % MEMORY_SIZE] = value;
+    }
+
+    /// The RAM address of `static index` in `file`, if the program uses it.
+    pub fn static_address(&self, file: &str, index: usize) -> Option<usize> {
+        self.statics
+            .get(&static_symbol(file, &index.to_string()))
+            .copied()
+    }
+
+    /// The next command to run, or `None` once the program has run off its
+    /// end.
+    pub fn current_command(&self) -> Option<&Command> {
+        self.commands.get(self.pc)
+    }
+
+    /// True past the last command, or on a `goto` back to a label with
+    /// nothing but labels in between, such as `label END` / `goto END`.
+    pub fn is_halted(&self) -> bool {
+        let Some(command) = self.current_command() else {
+            return true;
+        };
+        if command.instruction_type != InstructionType::Goto {
+            return false;
+        }
+
+        self.targets[self.pc].is_some_and(|target| {
+            target <= self.pc
+                && self.commands[target..self.pc]
+                    .iter()
+                    .all(|command| command.instruction_type == InstructionType::Label)
+        })
+    }
+
+    /// Runs until the program halts or `max_steps` commands have run.
+    pub fn run(&mut self, max_steps: u64) -> Result<RunOutcome, VmError> {
+        for _ in 0..max_steps {
+            if self.is_halted() {
+                return Ok(RunOutcome::Halted { steps: self.steps });
+            }
+            self.step()?;
+        }
+
+        if self.is_halted() {
+            Ok(RunOutcome::Halted { steps: self.steps })
+        } else {
+            Ok(RunOutcome::StepLimit { steps: self.steps })
+        }
+    }
+
+    fn push(&mut self, value: u16) {
+        let sp = self.memory[SP] as usize;
+        self.poke(sp, value);
+        self.memory[SP] = self.memory[SP].wrapping_add(1);
+    }
+
+    fn pop(&mut self) -> u16 {
+        self.memory[SP] = self.memory[SP].wrapping_sub(1);
+        self.peek(self.memory[SP] as usize)
+    }
+
+    fn segment_address(&self, command: &Command, index: usize) -> usize {
+        let based = |pointer: usize| (self.memory[pointer] as usize).wrapping_add(index);
+
+        match command.arg1.as_str() {
+            "local" => based(LCL),
+            "argument" => based(ARG),
+            "this" => based(THIS),
+            "that" => based(THAT),
+            "pointer" => THIS + index,
+            "temp" => TEMP + index,
+            "static" => self.statics[&static_symbol(&command.file, &command.arg2)],
+            _ => unreachable!(),
+        }
+    }
+
+    fn arithmetic(&mut self, operation: &str) {
+        let compare = |condition: bool| if condition { TRUE } else { FALSE };
+
+        if operation == "neg" || operation == "not" {
+            let x = self.pop();
+            let result = if operation == "neg" {
+                x.wrapping_neg()
+            } else {
+                !x
+            };
+            self.push(result);
+            return;
+        }
+
+        let y = self.pop();
+        let x = self.pop();
+        let result = match operation {
+            "add" => x.wrapping_add(y),
+            "sub" => x.wrapping_sub(y),
+            "and" => x & y,
+            "or" => x | y,
+            "eq" => compare(x == y),
+            "lt" => compare((x as i16) < (y as i16)),
+            "gt" => compare((x as i16) > (y as i16)),
+            _ => unreachable!(),
+        };
+        self.push(result);
+    }
+
+    fn call(&mut self, name: &str, n_args: usize, location: &str) -> Result<(), VmError> {
+        let Some(&address) = self.functions.get(name) else {
+            return Err(VmError::UndefinedFunction(
+                name.to_string(),
+                location.to_string(),
+            ));
+        };
+
+        self.push(self.pc as u16);
+        for pointer in [LCL, ARG, THIS, THAT] {
+            self.push(self.memory[pointer]);
+        }
+        self.memory[ARG] = self.memory[SP].wrapping_sub(n_args as u16 + 5);
+        self.memory[LCL] = self.memory[SP];
+        self.pc = address;
+
+        Ok(())
+    }
+
+    fn r#return(&mut self) {
+        let frame = self.memory[LCL] as usize;
+        let return_address = self.peek(frame.wrapping_sub(5));
+
+        let value = self.pop();
+        let arg = self.memory[ARG] as usize;
+        self.poke(arg, value);
+        self.memory[SP] = self.memory[ARG].wrapping_add(1);
+
+        for (offset, pointer) in [THAT, THIS, ARG, LCL].into_iter().enumerate() {
+            self.memory[pointer] = self.peek(frame.wrapping_sub(offset + 1));
+        }
+        self.pc = return_address as usize;
+    }
+
+    /// Runs one command; does nothing once the program has run off its end.
+    pub fn step(&mut self) -> Result<(), VmError> {
+        let Some(command) = self.commands.get(self.pc).cloned() else {
+            return Ok(());
+        };
+        let target = self.targets[self.pc];
+        self.pc += 1;
+        self.steps += 1;
+
+        match command.instruction_type {
+            InstructionType::Arithmetic => self.arithmetic(&command.arg1),
+            InstructionType::Push => {
+                let index: usize = command.arg2.parse().expect("push index");
+                let value = if command.arg1 == "constant" {
+                    index as u16
+                } else {
+                    self.peek(self.segment_address(&command, index))
+                };
+                self.push(value);
+            }
+            InstructionType::Pop => {
+                let index: usize = command.arg2.parse().expect("pop index");
+                let address = self.segment_address(&command, index);
+                let value = self.pop();
+                self.poke(address, value);
+            }
+            InstructionType::Label => {}
+            InstructionType::Goto => self.pc = target.expect("goto target"),
+            InstructionType::IfGoto => {
+                if self.pop() != 0 {
+                    self.pc = target.expect("if-goto target");
+                }
+            }
+            InstructionType::Function => {
+                let n_vars: usize = command.arg2.parse().expect("function nVars");
+                for _ in 0..n_vars {
+                    self.push(0);
+                }
+            }
+            InstructionType::Call => {
+                let n_args: usize = command.arg2.parse().expect("call nArgs");
+                let location = format!("{}:{}", command.file, command.line);
+                self.call(&command.arg1, n_args, &location)?;
+            }
+            InstructionType::Return => self.r#return(),
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,4 @@
+pub mod code_writer;
+pub mod emulator;
+pub mod optimizer;
+pub mod parser;
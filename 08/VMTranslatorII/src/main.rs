@@ -1,19 +1,62 @@
 use std::env;
-
-mod code_writer;
-mod parser;
+use std::fs;
+use std::path::Path;
+use std::process;
+use vm_translator::parser::collect_input_filepaths;
+use vm_translator::{code_writer, parser};
 
 fn main() {
-    let args: Vec<String> = env::args().collect();
+    let (flags, args): (Vec<String>, Vec<String>) =
+        env::args().partition(|arg| arg.starts_with("--"));
     if args.len() != 3 {
-        println!("Usage: cargo run -- <input filename> <output filename>");
+        println!(
+            "Usage: cargo run -- <input file or directory> <output filename> [--bootstrap|--no-bootstrap] [--optimize] [--shared-subroutines]"
+        );
         return;
     }
 
-    let mut parser: parser::Parser = parser::Parser::new(
-        args[1].clone(),
-        code_writer::CodeWriter::new(args[2].clone()),
-    );
+    let input_path = Path::new(&args[1]);
+
+    // Directories are translated with bootstrap code by default, single files without
+    let mut bootstrap: bool = input_path.is_dir();
+    let mut optimize = false;
+    let mut shared_subroutines = false;
+    for flag in &flags {
+        match flag.as_str() {
+            "--bootstrap" => bootstrap = true,
+            "--no-bootstrap" => bootstrap = false,
+            "--optimize" => optimize = true,
+            "--shared-subroutines" => shared_subroutines = true,
+            _ => {
+                println!("Unknown option: {}", flag);
+                return;
+            }
+        }
+    }
+
+    let input_filepaths = collect_input_filepaths(input_path).expect("input files");
+
+    let mut writer = code_writer::CodeWriter::new(args[2].clone());
+    writer.set_optimize(optimize);
+    writer.set_shared_subroutines(shared_subroutines);
+    let mut parser: parser::Parser = parser::Parser::new(input_filepaths, writer);
 
-    parser.parse().ok();
+    if let Err(e) = parser.parse(bootstrap) {
+        eprintln!("Error translating {}: {}", args[1], e);
+        process::exit(1);
+    }
+
+    let diagnostics = parser.diagnostics();
+    if !diagnostics.is_empty() {
+        eprintln!("{}", diagnostics);
+        eprintln!(
+            "error: could not translate '{}' due to {} previous error(s)",
+            args[1],
+            diagnostics.len()
+        );
+
+        // Don't leave a partially translated program behind
+        fs::remove_file(&args[2]).ok();
+        process::exit(1);
+    }
 }
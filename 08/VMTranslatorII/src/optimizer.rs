@@ -0,0 +1,129 @@
+/// Peephole optimization of the generated assembly. Every rewrite keeps the
+/// observable behaviour of the VM program: the stack, the segments and the
+/// registers that are live at command boundaries. Labels are never moved or
+/// removed, so jump targets stay valid; patterns never span a label.
+///
+/// The rewrites, applied until none matches:
+/// - `push constant c` followed by `add`, `sub`, `and` or `or` operates on
+///   the stack top in place instead of pushing and popping `c`
+/// - an `SP++` directly followed by `SP--` cancels out
+/// - reloading the value just stored to the stack top is dropped
+/// - a store to the free slot above the stack top is dropped once nothing
+///   can read it
+/// - `M=M-1` followed by `A=M` becomes `AM=M-1`
+/// - an `@X` load immediately overwritten by another is dropped
+pub fn optimize(lines: Vec<String>) -> Vec<String> {
+    let mut code: Vec<String> = lines
+        .into_iter()
+        .map(|line| line.trim().to_string())
+        .filter(|line| !line.is_empty())
+        .collect();
+
+    loop {
+        let mut changed = false;
+        let mut optimized: Vec<String> = Vec::with_capacity(code.len());
+        let mut i = 0;
+
+        while i < code.len() {
+            match rewrite(&code[i..]) {
+                Some((consumed, replacement)) => {
+                    optimized.extend(replacement);
+                    i += consumed;
+                    changed = true;
+                }
+                None => {
+                    optimized.push(code[i].clone());
+                    i += 1;
+                }
+            }
+        }
+
+        code = optimized;
+        if !changed {
+            return code;
+        }
+    }
+}
+
+/// Matches `pattern` at the start of `code`. A `{}` in the pattern matches
+/// one operand and is returned.
+fn matches<'a>(code: &'a [String], pattern: &[&str]) -> Option<Vec<&'a str>> {
+    if code.len() < pattern.len() {
+        return None;
+    }
+
+    let mut operands = Vec::new();
+    for (line, expected) in code.iter().zip(pattern) {
+        match expected.split_once("{}") {
+            Some((prefix, suffix)) => {
+                let operand = line.strip_prefix(prefix)?.strip_suffix(suffix)?;
+                if operand.is_empty() {
+                    return None;
+                }
+                operands.push(operand);
+            }
+            None if line == expected => {}
+            None => return None,
+        }
+    }
+
+    Some(operands)
+}
+
+const PUSH_D: [&str; 5] = ["@SP", "A=M", "M=D", "@SP", "M=M+1"];
+
+/// The first rewrite that applies at the start of `code`: how many lines it
+/// consumes and what replaces them.
+fn rewrite(code: &[String]) -> Option<(usize, Vec<String>)> {
+    let lines = |replacement: &[&str]| replacement.iter().map(|line| line.to_string()).collect();
+
+    // push constant c; add|sub|and|or
+    let mut fold = vec!["@{}", "D=A"];
+    fold.extend(PUSH_D);
+    fold.extend([
+        "@SP", "M=M-1", "A=M", "D=M", "@SP", "M=M-1", "A=M", "M=M{}", "@SP", "M=M+1",
+    ]);
+    if let Some(operands) = matches(code, &fold) {
+        let (constant, operation) = (operands[0], operands[1]);
+        if ["+D", "-D", "&D", "|D"].contains(&operation) {
+            let replacement = vec![
+                format!("@{}", constant),
+                "D=A".to_string(),
+                "@SP".to_string(),
+                "A=M-1".to_string(),
+                format!("M=M{}", operation),
+            ];
+            return Some((fold.len(), replacement));
+        }
+    }
+
+    // SP++ then SP--: the stack pointer is unchanged and A is still SP
+    if matches(code, &["@SP", "M=M+1", "@SP", "M=M-1"]).is_some() {
+        return Some((4, lines(&["@SP"])));
+    }
+    if matches(code, &["@SP", "M=M+1", "@SP", "AM=M-1"]).is_some() {
+        return Some((4, lines(&["@SP", "A=M"])));
+    }
+
+    // D already holds the value just stored at RAM[SP]
+    if matches(code, &["@SP", "A=M", "M=D", "@SP", "A=M", "D=M"]).is_some() {
+        return Some((6, lines(&["@SP", "A=M", "M=D"])));
+    }
+
+    // RAM[SP] is above the stack top and every template writes it before
+    // reading, so a store there that isn't followed by SP++ is dead
+    if matches(code, &["@SP", "A=M", "M=D", "@{}"]).is_some_and(|operands| operands[0] != "SP") {
+        return Some((3, Vec::new()));
+    }
+
+    if matches(code, &["M=M-1", "A=M"]).is_some() {
+        return Some((2, lines(&["AM=M-1"])));
+    }
+
+    // Dead A-register load
+    if matches(code, &["@{}", "@{}"]).is_some() {
+        return Some((1, Vec::new()));
+    }
+
+    None
+}
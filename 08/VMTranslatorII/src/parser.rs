@@ -1,9 +1,18 @@
 use crate::code_writer;
+use hack_core::diagnostics::{Diagnostic, Diagnostics};
+use std::collections::HashSet;
 use std::fmt;
 use std::fs::File;
 use std::io::{self, BufRead, BufReader, Lines};
+use std::path::Path;
 
-#[derive(Debug, PartialEq)]
+const SEGMENTS: [&str; 8] = [
+    "argument", "local", "static", "constant", "this", "that", "pointer", "temp",
+];
+const ARITHMETIC_COMMANDS: [&str; 9] = ["add", "sub", "neg", "eq", "gt", "lt", "and", "or", "not"];
+const MAX_CONSTANT: usize = 0x7FFF;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum InstructionType {
     Arithmetic,
     Push,
@@ -11,42 +20,197 @@ pub enum InstructionType {
     Label,
     Goto,
     IfGoto,
-    //Function,
-    //Return,
-    //Call,
+    Function,
+    Return,
+    Call,
+}
+
+#[derive(Debug)]
+pub enum ParserError {
+    UnknownCommand(String),
+    UnknownSegment(String),
+    InvalidIndex(String),
+    MissingArgument(String),
+    UnexpectedArgument(String),
+    ReadOnlySegment(String),
+    UndefinedLabel(String, String),
+}
+
+impl ParserError {
+    fn text(&self) -> &str {
+        match self {
+            ParserError::UnknownCommand(text)
+            | ParserError::UnknownSegment(text)
+            | ParserError::InvalidIndex(text)
+            | ParserError::MissingArgument(text)
+            | ParserError::UnexpectedArgument(text)
+            | ParserError::ReadOnlySegment(text)
+            | ParserError::UndefinedLabel(text, _) => text,
+        }
+    }
+}
+
+impl fmt::Display for ParserError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParserError::UnknownCommand(c) => write!(f, "Unknown command '{}'", c),
+            ParserError::UnknownSegment(s) => write!(f, "Unknown memory segment '{}'", s),
+            ParserError::InvalidIndex(i) => write!(f, "Invalid index '{}'", i),
+            ParserError::MissingArgument(c) => write!(f, "Missing argument for '{}'", c),
+            ParserError::UnexpectedArgument(a) => write!(f, "Unexpected argument '{}'", a),
+            ParserError::ReadOnlySegment(s) => write!(f, "Cannot pop into segment '{}'", s),
+            ParserError::UndefinedLabel(l, func) => {
+                write!(f, "Label '{}' is not defined in function '{}'", l, func)
+            }
+        }
+    }
+}
+
+/// A validated VM command and the file it came from. Push/pop indices and
+/// function/call counts are normalized to plain decimal.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Command {
+    pub instruction_type: InstructionType,
+    pub arg1: String,
+    pub arg2: String,
+    pub file: String,
+    pub line: usize,
+}
+
+/// The `.vm` files to translate for `input_path`: the file itself, or every
+/// `.vm` file in the directory in name order.
+pub fn collect_input_filepaths(input_path: &Path) -> Result<Vec<String>, std::io::Error> {
+    if !input_path.is_dir() {
+        return Ok(vec![input_path.to_string_lossy().to_string()]);
+    }
+
+    let mut input_filepaths: Vec<String> = std::fs::read_dir(input_path)?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "vm"))
+        .map(|path| path.to_string_lossy().to_string())
+        .collect();
+    input_filepaths.sort();
+
+    Ok(input_filepaths)
+}
+
+struct GotoTarget {
+    label: String,
+    line_number: usize,
+    column: usize,
+    line: String,
 }
 
 pub struct Parser {
-    input_filepath: String,
-    writer: code_writer::CodeWriter,
+    input_filepaths: Vec<String>,
+    writer: Option<code_writer::CodeWriter>,
+    diagnostics: Diagnostics,
+    current_function: String,
+    defined_labels: HashSet<String>,
+    goto_targets: Vec<GotoTarget>,
 }
 
 impl fmt::Debug for Parser {
     fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
-        write!(f, "Parser: {}", self.input_filepath)
+        write!(f, "Parser: {}", self.input_filepaths.join(", "))
     }
 }
 
 impl Parser {
-    pub fn new(input_filepath: String, writer: code_writer::CodeWriter) -> Self {
+    pub fn new(input_filepaths: Vec<String>, writer: code_writer::CodeWriter) -> Self {
+        Self {
+            writer: Some(writer),
+            ..Self::without_writer(input_filepaths)
+        }
+    }
+
+    /// A parser that only reads and validates commands, see [`Parser::commands`].
+    pub fn without_writer(input_filepaths: Vec<String>) -> Self {
         Self {
-            input_filepath: input_filepath,
-            writer: writer,
+            input_filepaths,
+            writer: None,
+            diagnostics: Diagnostics::new(),
+            current_function: String::new(),
+            defined_labels: HashSet::<String>::new(),
+            goto_targets: Vec::<GotoTarget>::new(),
         }
     }
 
+    pub fn diagnostics(&self) -> &Diagnostics {
+        &self.diagnostics
+    }
+
     fn clean_line(&self, line: &str) -> String {
         line.split_once("//")
             .map(|(before, _)| before)
-            .unwrap_or(&line)
+            .unwrap_or(line)
             .trim()
             .to_string()
     }
 
-    pub fn parse(&mut self) -> Result<(), std::io::Error> {
-        let lines = self.read_lines()?;
+    /// The 1-based column of each word before any `//` comment.
+    fn word_columns(&self, line: &str) -> Vec<usize> {
+        let code = line.split_once("//").map(|(before, _)| before).unwrap_or(line);
+
+        code.char_indices()
+            .filter(|&(i, c)| {
+                !c.is_whitespace()
+                    && code[..i].chars().next_back().is_none_or(char::is_whitespace)
+            })
+            .map(|(i, _)| i + 1)
+            .collect()
+    }
+
+    /// Reads every input file. Commands that fail to parse are reported in
+    /// [`Parser::diagnostics`] and left out.
+    pub fn commands(&mut self) -> Result<Vec<Command>, std::io::Error> {
+        let mut commands: Vec<Command> = Vec::new();
+
+        for input_filepath in self.input_filepaths.clone() {
+            commands.extend(self.parse_file(&input_filepath)?);
+        }
+
+        Ok(commands)
+    }
+
+    pub fn parse(&mut self, bootstrap: bool) -> Result<(), std::io::Error> {
+        let commands = self.commands()?;
+        let writer = self.writer.as_mut().expect("parser without a code writer");
+
+        if bootstrap {
+            writer.write_init()?;
+        }
+
+        let mut current_file: Option<String> = None;
+        for command in commands {
+            if current_file.as_ref() != Some(&command.file) {
+                writer.set_file_name(&command.file);
+                current_file = Some(command.file.clone());
+            }
+
+            let Command { instruction_type, arg1, arg2, .. } = command;
+            match instruction_type {
+                InstructionType::Arithmetic => writer.write_arithmetic_or_logical(arg1)?,
+                InstructionType::Label => writer.write_label(arg1)?,
+                InstructionType::Push | InstructionType::Pop =>
+                    writer.write_push_pop(arg1, arg2, instruction_type)?,
+                InstructionType::Goto => writer.write_goto(arg1)?,
+                InstructionType::IfGoto => writer.write_ifgoto(arg1)?,
+                InstructionType::Function => writer.write_function(arg1, arg2)?,
+                InstructionType::Call => writer.write_call(arg1, arg2)?,
+                InstructionType::Return => writer.write_return()?,
+            }
+        }
+
+        writer.close()
+    }
+
+    fn parse_file(&mut self, input_filepath: &str) -> Result<Vec<Command>, std::io::Error> {
+        let lines = self.read_lines(input_filepath)?;
+        let mut commands: Vec<Command> = Vec::new();
+        self.current_function = String::new();
 
-        for line in lines {
+        for (i, line) in lines.enumerate() {
             let line = line?;
             let current_instruction = self.clean_line(&line);
 
@@ -54,31 +218,154 @@ impl Parser {
                 continue;
             }
 
-            let instruction_type = self.command_type(&current_instruction);
-            let arg1 = self.arg_n(&current_instruction, &instruction_type, true);
-            let arg2 = self.arg_n(&current_instruction, &instruction_type, false);
+            let columns = self.word_columns(&line);
+            let (instruction_type, arg1, arg2) = match self.parse_instruction(&current_instruction) {
+                Ok(parsed) => parsed,
+                Err((e, word)) => {
+                    let mut diagnostic =
+                        Diagnostic::new(input_filepath, i + 1, &line, e.text(), e.to_string());
+                    if let Some(&column) = columns.get(word) {
+                        diagnostic.column = column;
+                    }
+                    self.diagnostics.push(diagnostic);
+                    continue;
+                }
+            };
 
             match instruction_type {
-                InstructionType::Arithmetic => self.writer.write_arithmetic_or_logical(arg1)?,
-                InstructionType::Label => self.writer.write_label(arg1)?,
-                InstructionType::Push | InstructionType::Pop =>
-                    self.writer.write_push_pop(arg1, arg2, instruction_type)?,
-                InstructionType::Goto => self.writer.write_goto(arg1)?,
-                InstructionType::IfGoto => self.writer.write_ifgoto(arg1)?,
+                InstructionType::Label => {
+                    self.defined_labels.insert(arg1.clone());
+                }
+                InstructionType::Goto | InstructionType::IfGoto => {
+                    self.goto_targets.push(GotoTarget {
+                        label: arg1.clone(),
+                        line_number: i + 1,
+                        column: columns[1],
+                        line: line.clone(),
+                    });
+                }
+                InstructionType::Function => {
+                    self.end_function(input_filepath);
+                    self.current_function = arg1.clone();
+                }
+                _ => {}
+            }
+
+            commands.push(Command {
+                instruction_type,
+                arg1,
+                arg2,
+                file: input_filepath.to_string(),
+                line: i + 1,
+            });
+        }
+
+        self.end_function(input_filepath);
+
+        Ok(commands)
+    }
+
+    fn end_function(&mut self, input_filepath: &str) {
+        // Every goto/if-goto must target a label defined in the same function
+        for target in self.goto_targets.drain(..) {
+            if self.defined_labels.contains(&target.label) {
+                continue;
+            }
+
+            let e = ParserError::UndefinedLabel(target.label, self.current_function.clone());
+            let mut diagnostic = Diagnostic::new(
+                input_filepath,
+                target.line_number,
+                &target.line,
+                e.text(),
+                e.to_string(),
+            );
+            diagnostic.column = target.column;
+            self.diagnostics.push(diagnostic);
+        }
+
+        self.defined_labels.clear();
+    }
+
+    /// Errors come with the index of the word they point at.
+    fn parse_instruction(
+        &self,
+        instruction: &str,
+    ) -> Result<(InstructionType, String, String), (ParserError, usize)> {
+        let instruction_type = self.command_type(instruction).map_err(|e| (e, 0))?;
+        let arg1 = self.arg_n(instruction, &instruction_type, true);
+        let arg2 = self.arg_n(instruction, &instruction_type, false);
+
+        let command = instruction.split_whitespace().next().unwrap_or("");
+        let expected_args: usize = match instruction_type {
+            InstructionType::Arithmetic | InstructionType::Return => 0,
+            InstructionType::Label | InstructionType::Goto | InstructionType::IfGoto => 1,
+            InstructionType::Push
+            | InstructionType::Pop
+            | InstructionType::Function
+            | InstructionType::Call => 2,
+        };
+
+        let args: Vec<&str> = instruction.split_whitespace().skip(1).collect();
+        if args.len() < expected_args {
+            return Err((ParserError::MissingArgument(command.to_string()), 0));
+        }
+        if let Some(extra) = args.get(expected_args) {
+            return Err((ParserError::UnexpectedArgument(extra.to_string()), expected_args + 1));
+        }
+
+        match instruction_type {
+            InstructionType::Push | InstructionType::Pop => {
+                if !SEGMENTS.contains(&arg1.as_str()) {
+                    return Err((ParserError::UnknownSegment(arg1), 1));
+                }
+
+                if arg1 == "constant" && instruction_type == InstructionType::Pop {
+                    return Err((ParserError::ReadOnlySegment(arg1), 1));
+                }
+
+                let index = self.parse_index(&arg2).map_err(|e| (e, 2))?;
+                let index_in_range = match arg1.as_str() {
+                    "constant" => index <= MAX_CONSTANT,
+                    "pointer" => index <= 1,
+                    "temp" => index <= 7,
+                    _ => true,
+                };
+
+                if !index_in_range {
+                    return Err((ParserError::InvalidIndex(arg2), 2));
+                }
+
+                Ok((instruction_type, arg1, index.to_string()))
+            }
+            InstructionType::Function | InstructionType::Call => {
+                let count = self.parse_index(&arg2).map_err(|e| (e, 2))?;
+
+                Ok((instruction_type, arg1, count.to_string()))
             }
+            _ => Ok((instruction_type, arg1, arg2)),
         }
+    }
 
-        Ok(())
+    fn parse_index(&self, arg: &str) -> Result<usize, ParserError> {
+        arg.parse::<usize>()
+            .map_err(|_| ParserError::InvalidIndex(arg.to_string()))
     }
 
-    fn command_type(&self, instruction: &str) -> InstructionType {
+    fn command_type(&self, instruction: &str) -> Result<InstructionType, ParserError> {
         match instruction.split_whitespace().next() {
-            Some("push") => InstructionType::Push,
-            Some("pop") => InstructionType::Pop,
-            Some("label") => InstructionType::Label,
-            Some("goto") => InstructionType::Goto,
-            Some("if-goto") => InstructionType::IfGoto,
-            _ => InstructionType::Arithmetic,
+            Some("push") => Ok(InstructionType::Push),
+            Some("pop") => Ok(InstructionType::Pop),
+            Some("label") => Ok(InstructionType::Label),
+            Some("goto") => Ok(InstructionType::Goto),
+            Some("if-goto") => Ok(InstructionType::IfGoto),
+            Some("function") => Ok(InstructionType::Function),
+            Some("call") => Ok(InstructionType::Call),
+            Some("return") => Ok(InstructionType::Return),
+            Some(command) if ARITHMETIC_COMMANDS.contains(&command) => {
+                Ok(InstructionType::Arithmetic)
+            }
+            command => Err(ParserError::UnknownCommand(command.unwrap_or("").to_string())),
         }
     }
 
@@ -88,9 +375,13 @@ impl Parser {
         instruction_type: &InstructionType,
         first_arg: bool,
     ) -> String {
+        if *instruction_type == InstructionType::Return {
+            return String::new();
+        }
+
         if *instruction_type == InstructionType::Arithmetic {
             return if first_arg {
-                instruction.to_string()
+                instruction.split_whitespace().next().unwrap_or("").to_string()
             } else {
                 String::new()
             };
@@ -103,8 +394,8 @@ impl Parser {
             .to_string()
     }
 
-    fn read_lines(&self) -> io::Result<Lines<BufReader<File>>> {
-        let file = File::open(&self.input_filepath)?;
+    fn read_lines(&self, input_filepath: &str) -> io::Result<Lines<BufReader<File>>> {
+        let file = File::open(input_filepath)?;
 
         Ok(BufReader::new(file).lines())
     }
@@ -0,0 +1,103 @@
+use cpu_emulator::{Computer, RunOutcome};
+use std::fs;
+use vm_translator::code_writer::CodeWriter;
+use vm_translator::parser::Parser;
+
+const MAX_CYCLES: u64 = 10_000;
+const TRUE: i16 = -1;
+const FALSE: i16 = 0;
+
+/// `push constant` can't push negative values, so they are built with neg/sub.
+fn push(value: i16) -> String {
+    match value {
+        i16::MIN => "push constant 32767\nneg\npush constant 1\nsub\n".to_string(),
+        value if value < 0 => format!("push constant {}\nneg\n", -value),
+        value => format!("push constant {}\n", value),
+    }
+}
+
+/// Translates `x <command> y`, assembles it and runs it on the CPU
+/// emulator, returning what is left on top of the stack.
+fn compare(x: i16, y: i16, command: &str, shared_subroutines: bool) -> i16 {
+    let dir = std::env::temp_dir().join(format!(
+        "vm-compare-{}-{}-{}-{}-{}",
+        std::process::id(),
+        x,
+        y,
+        command,
+        shared_subroutines
+    ));
+    fs::create_dir_all(&dir).expect("scratch directory");
+    let input = dir.join("Compare.vm");
+    let output = dir.join("Compare.asm");
+
+    let source = format!("{}{}{}\nlabel END\ngoto END\n", push(x), push(y), command);
+    fs::write(&input, source).expect("write .vm file");
+
+    let mut writer = CodeWriter::new(output.to_string_lossy().to_string());
+    writer.set_shared_subroutines(shared_subroutines);
+    let mut parser = Parser::new(vec![input.to_string_lossy().to_string()], writer);
+    parser.parse(false).expect("translate");
+    assert!(parser.diagnostics().is_empty(), "{}", parser.diagnostics());
+
+    let asm = fs::read_to_string(&output).expect("read .asm file");
+    fs::remove_dir_all(&dir).ok();
+    let words = hack_asm::assemble(&asm).expect("assemble").words;
+
+    let mut computer = Computer::new(&words).expect("load program");
+    computer.poke(0, 256);
+    assert!(matches!(
+        computer.run(MAX_CYCLES),
+        RunOutcome::Halted { .. }
+    ));
+    assert_eq!(computer.peek(0), 257, "{} {} {}: SP", x, command, y);
+
+    computer.peek(256) as i16
+}
+
+/// Operand pairs whose difference overflows 16 bits, with the expected
+/// `lt` and `gt` results
+const EXTREMES: [(i16, i16, i16, i16); 6] = [
+    (-32767, 2, TRUE, FALSE),
+    (2, -32767, FALSE, TRUE),
+    (i16::MIN, 1, TRUE, FALSE),
+    (1, i16::MIN, FALSE, TRUE),
+    (i16::MAX, i16::MIN, FALSE, TRUE),
+    (i16::MIN, i16::MAX, TRUE, FALSE),
+];
+
+#[test]
+fn gt_of_negated_32767_and_2_is_false() {
+    // push constant 32767; neg; push constant 2; gt
+    assert_eq!(compare(-32767, 2, "gt", false), FALSE);
+    assert_eq!(compare(-32767, 2, "gt", true), FALSE);
+}
+
+#[test]
+fn lt_and_gt_handle_overflowing_operands() {
+    for shared_subroutines in [false, true] {
+        for (x, y, lt, gt) in EXTREMES {
+            let mode = if shared_subroutines {
+                "shared"
+            } else {
+                "inline"
+            };
+            assert_eq!(
+                compare(x, y, "lt", shared_subroutines),
+                lt,
+                "{} lt {} ({})",
+                x,
+                y,
+                mode
+            );
+            assert_eq!(
+                compare(x, y, "gt", shared_subroutines),
+                gt,
+                "{} gt {} ({})",
+                x,
+                y,
+                mode
+            );
+        }
+    }
+}
@@ -0,0 +1,40 @@
+use std::fs;
+use vm_translator::parser::Parser;
+
+/// Parses `source` as a single `.vm` file and returns the (line, column) of
+/// each diagnostic.
+fn positions(name: &str, source: &str) -> Vec<(usize, usize)> {
+    let dir = std::env::temp_dir().join(format!("vm-diagnostics-{}-{}", std::process::id(), name));
+    fs::create_dir_all(&dir).expect("scratch directory");
+    let input = dir.join("Test.vm");
+    fs::write(&input, source).expect("write .vm file");
+
+    let mut parser = Parser::without_writer(vec![input.to_string_lossy().to_string()]);
+    parser.commands().expect("read .vm file");
+    fs::remove_dir_all(&dir).ok();
+
+    parser
+        .diagnostics()
+        .iter()
+        .map(|diagnostic| (diagnostic.line, diagnostic.column))
+        .collect()
+}
+
+#[test]
+fn carets_point_at_the_offending_word() {
+    let source = "\
+function Test.f 0
+push constant 1 1
+pop local l
+push local 3 l
+\tpop  constant 2 // constant
+goto g
+push
+return
+";
+
+    assert_eq!(
+        positions("words", source),
+        vec![(2, 17), (3, 11), (4, 14), (5, 7), (7, 1), (6, 6)]
+    );
+}
@@ -0,0 +1,202 @@
+/// Where a token starts; `line` and `column` are 1-based.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Position {
+    pub line: usize,
+    pub column: usize,
+}
+
+/// A name used inside a subroutine body, kept with its position so the
+/// compiler can report errors at it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Identifier {
+    pub name: String,
+    pub position: Position,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Class {
+    pub name: String,
+    pub class_var_decs: Vec<ClassVarDec>,
+    pub subroutine_decs: Vec<SubroutineDec>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ClassVarKind {
+    Static,
+    Field,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClassVarDec {
+    pub kind: ClassVarKind,
+    pub var_type: Type,
+    pub names: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Type {
+    Int,
+    Char,
+    Boolean,
+    Class(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SubroutineKind {
+    Constructor,
+    Function,
+    Method,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SubroutineDec {
+    pub kind: SubroutineKind,
+    /// `None` for `void`
+    pub return_type: Option<Type>,
+    pub name: String,
+    pub parameters: Vec<Parameter>,
+    pub body: SubroutineBody,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Parameter {
+    pub var_type: Type,
+    pub name: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SubroutineBody {
+    pub var_decs: Vec<VarDec>,
+    pub statements: Vec<Statement>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct VarDec {
+    pub var_type: Type,
+    pub names: Vec<String>,
+}
+
+/// A statement and the line its keyword is on.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Statement {
+    pub kind: StatementKind,
+    pub line: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum StatementKind {
+    Let {
+        name: Identifier,
+        index: Option<Expression>,
+        value: Expression,
+    },
+    If {
+        condition: Expression,
+        then_statements: Vec<Statement>,
+        else_statements: Option<Vec<Statement>>,
+    },
+    While {
+        condition: Expression,
+        statements: Vec<Statement>,
+    },
+    Do(SubroutineCall),
+    Return(Option<Expression>),
+}
+
+/// `term (op term)*`; Jack has no operator precedence, so the operations
+/// apply left to right.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Expression {
+    pub term: Term,
+    pub operations: Vec<(BinaryOp, Term)>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BinaryOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    And,
+    Or,
+    Lt,
+    Gt,
+    Eq,
+}
+
+impl BinaryOp {
+    pub fn from_symbol(symbol: char) -> Option<BinaryOp> {
+        match symbol {
+            '+' => Some(BinaryOp::Add),
+            '-' => Some(BinaryOp::Sub),
+            '*' => Some(BinaryOp::Mul),
+            '/' => Some(BinaryOp::Div),
+            '&' => Some(BinaryOp::And),
+            '|' => Some(BinaryOp::Or),
+            '<' => Some(BinaryOp::Lt),
+            '>' => Some(BinaryOp::Gt),
+            '=' => Some(BinaryOp::Eq),
+            _ => None,
+        }
+    }
+
+    pub fn symbol(&self) -> char {
+        match self {
+            BinaryOp::Add => '+',
+            BinaryOp::Sub => '-',
+            BinaryOp::Mul => '*',
+            BinaryOp::Div => '/',
+            BinaryOp::And => '&',
+            BinaryOp::Or => '|',
+            BinaryOp::Lt => '<',
+            BinaryOp::Gt => '>',
+            BinaryOp::Eq => '=',
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum UnaryOp {
+    Neg,
+    Not,
+}
+
+impl UnaryOp {
+    pub fn symbol(&self) -> char {
+        match self {
+            UnaryOp::Neg => '-',
+            UnaryOp::Not => '~',
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum KeywordConstant {
+    True,
+    False,
+    Null,
+    This,
+}
+
+/// Parentheses are kept as [`Term::Parenthesized`] so the parse tree can be
+/// written back out exactly.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Term {
+    IntegerConstant(u16),
+    StringConstant(String),
+    KeywordConstant(KeywordConstant, Position),
+    Variable(Identifier),
+    ArrayElement(Identifier, Box<Expression>),
+    Call(SubroutineCall),
+    Parenthesized(Box<Expression>),
+    Unary(UnaryOp, Box<Term>),
+}
+
+/// `name(arguments)` or `receiver.name(arguments)`, where the receiver is a
+/// variable or a class name.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SubroutineCall {
+    pub receiver: Option<Identifier>,
+    pub name: Identifier,
+    pub arguments: Vec<Expression>,
+}
@@ -0,0 +1,558 @@
+use crate::ast::{
+    BinaryOp, Class, ClassVarDec, ClassVarKind, Expression, Identifier, KeywordConstant, Parameter,
+    Position, Statement, StatementKind, SubroutineBody, SubroutineCall, SubroutineDec,
+    SubroutineKind, Term, Type, UnaryOp, VarDec,
+};
+use crate::tokenizer::{Keyword, Token, TokenKind};
+use hack_core::diagnostics::{Diagnostic, Diagnostics};
+use std::fmt;
+
+#[derive(Debug)]
+pub enum ParserError {
+    Expected(String, String),
+    UnexpectedEnd(String),
+    TrailingInput(String),
+}
+
+impl fmt::Display for ParserError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParserError::Expected(expected, found) => {
+                write!(f, "Expected {} but found '{}'", expected, found)
+            }
+            ParserError::UnexpectedEnd(expected) => {
+                write!(f, "Expected {} but reached the end of the file", expected)
+            }
+            ParserError::TrailingInput(found) => {
+                write!(f, "Unexpected '{}' after the end of the class", found)
+            }
+        }
+    }
+}
+
+type ParseResult<T> = Result<T, Diagnostic>;
+
+/// Recursive-descent parser for one Jack class, one method per grammar
+/// rule. Parsing stops at the first syntax error.
+pub struct CompilationEngine<'a> {
+    file_name: String,
+    lines: Vec<&'a str>,
+    tokens: &'a [Token],
+    position: usize,
+}
+
+impl<'a> CompilationEngine<'a> {
+    pub fn new(tokens: &'a [Token], source: &'a str, file_name: &str) -> Self {
+        Self {
+            file_name: file_name.to_string(),
+            lines: source.lines().collect(),
+            tokens,
+            position: 0,
+        }
+    }
+
+    fn peek(&self) -> Option<&'a TokenKind> {
+        self.tokens.get(self.position).map(|token| &token.kind)
+    }
+
+    fn advance(&mut self) -> Option<&'a TokenKind> {
+        let kind = self.peek();
+        self.position += 1;
+        kind
+    }
+
+    /// Where the current token starts, or the last one once they run out.
+    fn position(&self) -> Position {
+        self.tokens
+            .get(self.position)
+            .or(self.tokens.last())
+            .map(|token| Position {
+                line: token.line,
+                column: token.column,
+            })
+            .unwrap_or(Position { line: 1, column: 1 })
+    }
+
+    fn is_symbol(&self, symbol: char) -> bool {
+        self.peek() == Some(&TokenKind::Symbol(symbol))
+    }
+
+    fn is_keyword(&self, keyword: Keyword) -> bool {
+        self.peek() == Some(&TokenKind::Keyword(keyword))
+    }
+
+    fn diagnostic(&self, token: &Token, e: ParserError) -> Diagnostic {
+        let source_line = self.lines.get(token.line - 1).copied().unwrap_or("");
+        let text = token.kind.to_string();
+        let mut diagnostic = Diagnostic::new(
+            &self.file_name,
+            token.line,
+            source_line,
+            &text,
+            e.to_string(),
+        );
+        diagnostic.column = token.column;
+        diagnostic
+    }
+
+    /// An error for the current token, which isn't `expected`.
+    fn error(&self, expected: &str) -> Diagnostic {
+        match self.tokens.get(self.position) {
+            Some(token) => self.diagnostic(
+                token,
+                ParserError::Expected(expected.to_string(), token.kind.to_string()),
+            ),
+            None => {
+                // Point just past the last token
+                let line = self.tokens.last().map(|token| token.line).unwrap_or(1);
+                let source_line = self.lines.get(line - 1).copied().unwrap_or("");
+                let mut diagnostic = Diagnostic::new(
+                    &self.file_name,
+                    line,
+                    source_line,
+                    "",
+                    ParserError::UnexpectedEnd(expected.to_string()).to_string(),
+                );
+                diagnostic.column = source_line.trim_end().len() + 1;
+                diagnostic
+            }
+        }
+    }
+
+    fn expect_symbol(&mut self, symbol: char) -> ParseResult<()> {
+        if !self.is_symbol(symbol) {
+            return Err(self.error(&format!("'{}'", symbol)));
+        }
+        self.advance();
+
+        Ok(())
+    }
+
+    fn expect_keyword(&mut self, keyword: Keyword) -> ParseResult<()> {
+        if !self.is_keyword(keyword) {
+            return Err(self.error(&format!("'{}'", keyword.as_str())));
+        }
+        self.advance();
+
+        Ok(())
+    }
+
+    fn expect_identifier(&mut self, what: &str) -> ParseResult<String> {
+        match self.peek() {
+            Some(TokenKind::Identifier(name)) => {
+                self.advance();
+                Ok(name.clone())
+            }
+            _ => Err(self.error(what)),
+        }
+    }
+
+    /// Same as [`CompilationEngine::expect_identifier`], keeping where the
+    /// name is.
+    fn expect_name(&mut self, what: &str) -> ParseResult<Identifier> {
+        let position = self.position();
+        let name = self.expect_identifier(what)?;
+
+        Ok(Identifier { name, position })
+    }
+
+    /// `'class' className '{' classVarDec* subroutineDec* '}'`, which must
+    /// be all there is in the file.
+    pub fn compile_class(&mut self) -> Result<Class, Diagnostics> {
+        let into_diagnostics = |diagnostic: Diagnostic| {
+            let mut diagnostics = Diagnostics::new();
+            diagnostics.push(diagnostic);
+            diagnostics
+        };
+
+        let class = self.class().map_err(into_diagnostics)?;
+        if let Some(token) = self.tokens.get(self.position) {
+            let e = ParserError::TrailingInput(token.kind.to_string());
+            return Err(into_diagnostics(self.diagnostic(token, e)));
+        }
+
+        Ok(class)
+    }
+
+    fn class(&mut self) -> ParseResult<Class> {
+        self.expect_keyword(Keyword::Class)?;
+        let name = self.expect_identifier("a class name")?;
+        self.expect_symbol('{')?;
+
+        let mut class_var_decs = Vec::new();
+        while self.is_keyword(Keyword::Static) || self.is_keyword(Keyword::Field) {
+            class_var_decs.push(self.compile_class_var_dec()?);
+        }
+
+        let mut subroutine_decs = Vec::new();
+        while self.is_keyword(Keyword::Constructor)
+            || self.is_keyword(Keyword::Function)
+            || self.is_keyword(Keyword::Method)
+        {
+            subroutine_decs.push(self.compile_subroutine()?);
+        }
+
+        if !self.is_symbol('}') {
+            return Err(self.error("a field, static, subroutine or '}'"));
+        }
+        self.advance();
+
+        Ok(Class {
+            name,
+            class_var_decs,
+            subroutine_decs,
+        })
+    }
+
+    /// `('static' | 'field') type varName (',' varName)* ';'`
+    fn compile_class_var_dec(&mut self) -> ParseResult<ClassVarDec> {
+        let kind = match self.advance() {
+            Some(TokenKind::Keyword(Keyword::Static)) => ClassVarKind::Static,
+            _ => ClassVarKind::Field,
+        };
+        let var_type = self.compile_type()?;
+        let names = self.compile_var_names()?;
+
+        Ok(ClassVarDec {
+            kind,
+            var_type,
+            names,
+        })
+    }
+
+    /// `'int' | 'char' | 'boolean' | className`
+    fn compile_type(&mut self) -> ParseResult<Type> {
+        let var_type = match self.peek() {
+            Some(TokenKind::Keyword(Keyword::Int)) => Type::Int,
+            Some(TokenKind::Keyword(Keyword::Char)) => Type::Char,
+            Some(TokenKind::Keyword(Keyword::Boolean)) => Type::Boolean,
+            Some(TokenKind::Identifier(name)) => Type::Class(name.clone()),
+            _ => return Err(self.error("a type")),
+        };
+        self.advance();
+
+        Ok(var_type)
+    }
+
+    /// `varName (',' varName)* ';'`
+    fn compile_var_names(&mut self) -> ParseResult<Vec<String>> {
+        let mut names = vec![self.expect_identifier("a variable name")?];
+        while self.is_symbol(',') {
+            self.advance();
+            names.push(self.expect_identifier("a variable name")?);
+        }
+        self.expect_symbol(';')?;
+
+        Ok(names)
+    }
+
+    /// `('constructor' | 'function' | 'method') ('void' | type)
+    /// subroutineName '(' parameterList ')' subroutineBody`
+    fn compile_subroutine(&mut self) -> ParseResult<SubroutineDec> {
+        let kind = match self.advance() {
+            Some(TokenKind::Keyword(Keyword::Constructor)) => SubroutineKind::Constructor,
+            Some(TokenKind::Keyword(Keyword::Function)) => SubroutineKind::Function,
+            _ => SubroutineKind::Method,
+        };
+
+        let return_type = if self.is_keyword(Keyword::Void) {
+            self.advance();
+            None
+        } else {
+            Some(self.compile_type()?)
+        };
+
+        let name = self.expect_identifier("a subroutine name")?;
+        self.expect_symbol('(')?;
+        let parameters = self.compile_parameter_list()?;
+        self.expect_symbol(')')?;
+        let body = self.compile_subroutine_body()?;
+
+        Ok(SubroutineDec {
+            kind,
+            return_type,
+            name,
+            parameters,
+            body,
+        })
+    }
+
+    /// `((type varName) (',' type varName)*)?`
+    fn compile_parameter_list(&mut self) -> ParseResult<Vec<Parameter>> {
+        let mut parameters = Vec::new();
+        if self.is_symbol(')') {
+            return Ok(parameters);
+        }
+
+        loop {
+            let var_type = self.compile_type()?;
+            let name = self.expect_identifier("a parameter name")?;
+            parameters.push(Parameter { var_type, name });
+
+            if !self.is_symbol(',') {
+                return Ok(parameters);
+            }
+            self.advance();
+        }
+    }
+
+    /// `'{' varDec* statements '}'`
+    fn compile_subroutine_body(&mut self) -> ParseResult<SubroutineBody> {
+        self.expect_symbol('{')?;
+
+        let mut var_decs = Vec::new();
+        while self.is_keyword(Keyword::Var) {
+            var_decs.push(self.compile_var_dec()?);
+        }
+
+        let statements = self.compile_statements()?;
+        self.expect_symbol('}')?;
+
+        Ok(SubroutineBody {
+            var_decs,
+            statements,
+        })
+    }
+
+    /// `'var' type varName (',' varName)* ';'`
+    fn compile_var_dec(&mut self) -> ParseResult<VarDec> {
+        self.expect_keyword(Keyword::Var)?;
+        let var_type = self.compile_type()?;
+        let names = self.compile_var_names()?;
+
+        Ok(VarDec { var_type, names })
+    }
+
+    /// `statement*`, up to the closing `'}'`
+    fn compile_statements(&mut self) -> ParseResult<Vec<Statement>> {
+        let mut statements = Vec::new();
+
+        loop {
+            let Some(token) = self.tokens.get(self.position) else {
+                return Err(self.error("a statement or '}'"));
+            };
+            let line = token.line;
+
+            let kind = match self.peek() {
+                Some(TokenKind::Keyword(Keyword::Let)) => self.compile_let()?,
+                Some(TokenKind::Keyword(Keyword::If)) => self.compile_if()?,
+                Some(TokenKind::Keyword(Keyword::While)) => self.compile_while()?,
+                Some(TokenKind::Keyword(Keyword::Do)) => self.compile_do()?,
+                Some(TokenKind::Keyword(Keyword::Return)) => self.compile_return()?,
+                Some(TokenKind::Symbol('}')) => return Ok(statements),
+                _ => return Err(self.error("a statement or '}'")),
+            };
+            statements.push(Statement { kind, line });
+        }
+    }
+
+    /// `'let' varName ('[' expression ']')? '=' expression ';'`
+    fn compile_let(&mut self) -> ParseResult<StatementKind> {
+        self.expect_keyword(Keyword::Let)?;
+        let name = self.expect_name("a variable name")?;
+
+        let index = if self.is_symbol('[') {
+            self.advance();
+            let index = self.compile_expression()?;
+            self.expect_symbol(']')?;
+            Some(index)
+        } else {
+            None
+        };
+
+        self.expect_symbol('=')?;
+        let value = self.compile_expression()?;
+        self.expect_symbol(';')?;
+
+        Ok(StatementKind::Let { name, index, value })
+    }
+
+    /// `'{' statements '}'`
+    fn compile_block(&mut self) -> ParseResult<Vec<Statement>> {
+        self.expect_symbol('{')?;
+        let statements = self.compile_statements()?;
+        self.expect_symbol('}')?;
+
+        Ok(statements)
+    }
+
+    /// `'(' expression ')'`
+    fn compile_condition(&mut self) -> ParseResult<Expression> {
+        self.expect_symbol('(')?;
+        let condition = self.compile_expression()?;
+        self.expect_symbol(')')?;
+
+        Ok(condition)
+    }
+
+    /// `'if' '(' expression ')' '{' statements '}' ('else' '{' statements '}')?`
+    fn compile_if(&mut self) -> ParseResult<StatementKind> {
+        self.expect_keyword(Keyword::If)?;
+        let condition = self.compile_condition()?;
+        let then_statements = self.compile_block()?;
+
+        let else_statements = if self.is_keyword(Keyword::Else) {
+            self.advance();
+            Some(self.compile_block()?)
+        } else {
+            None
+        };
+
+        Ok(StatementKind::If {
+            condition,
+            then_statements,
+            else_statements,
+        })
+    }
+
+    /// `'while' '(' expression ')' '{' statements '}'`
+    fn compile_while(&mut self) -> ParseResult<StatementKind> {
+        self.expect_keyword(Keyword::While)?;
+        let condition = self.compile_condition()?;
+        let statements = self.compile_block()?;
+
+        Ok(StatementKind::While {
+            condition,
+            statements,
+        })
+    }
+
+    /// `'do' subroutineCall ';'`
+    fn compile_do(&mut self) -> ParseResult<StatementKind> {
+        self.expect_keyword(Keyword::Do)?;
+        let name = self.expect_name("a subroutine call")?;
+        let call = self.compile_subroutine_call(name)?;
+        self.expect_symbol(';')?;
+
+        Ok(StatementKind::Do(call))
+    }
+
+    /// `'return' expression? ';'`
+    fn compile_return(&mut self) -> ParseResult<StatementKind> {
+        self.expect_keyword(Keyword::Return)?;
+
+        let value = if self.is_symbol(';') {
+            None
+        } else {
+            Some(self.compile_expression()?)
+        };
+        self.expect_symbol(';')?;
+
+        Ok(StatementKind::Return(value))
+    }
+
+    /// `term (op term)*`
+    fn compile_expression(&mut self) -> ParseResult<Expression> {
+        let term = self.compile_term()?;
+
+        let mut operations = Vec::new();
+        while let Some(op) = match self.peek() {
+            Some(TokenKind::Symbol(symbol)) => BinaryOp::from_symbol(*symbol),
+            _ => None,
+        } {
+            self.advance();
+            operations.push((op, self.compile_term()?));
+        }
+
+        Ok(Expression { term, operations })
+    }
+
+    /// `integerConstant | stringConstant | keywordConstant | varName |
+    /// varName '[' expression ']' | subroutineCall | '(' expression ')' |
+    /// unaryOp term`; an identifier is told apart by the token after it.
+    fn compile_term(&mut self) -> ParseResult<Term> {
+        let position = self.position();
+        let term = match self.peek() {
+            Some(TokenKind::IntegerConstant(value)) => Term::IntegerConstant(*value),
+            Some(TokenKind::StringConstant(text)) => Term::StringConstant(text.clone()),
+            Some(TokenKind::Keyword(keyword)) => {
+                let constant = match keyword {
+                    Keyword::True => KeywordConstant::True,
+                    Keyword::False => KeywordConstant::False,
+                    Keyword::Null => KeywordConstant::Null,
+                    Keyword::This => KeywordConstant::This,
+                    _ => return Err(self.error("an expression")),
+                };
+                Term::KeywordConstant(constant, position)
+            }
+            Some(TokenKind::Identifier(name)) => {
+                self.advance();
+                let name = Identifier {
+                    name: name.clone(),
+                    position,
+                };
+
+                return match self.peek() {
+                    Some(TokenKind::Symbol('[')) => {
+                        self.advance();
+                        let index = self.compile_expression()?;
+                        self.expect_symbol(']')?;
+                        Ok(Term::ArrayElement(name, Box::new(index)))
+                    }
+                    Some(TokenKind::Symbol('(' | '.')) => {
+                        Ok(Term::Call(self.compile_subroutine_call(name)?))
+                    }
+                    _ => Ok(Term::Variable(name)),
+                };
+            }
+            Some(TokenKind::Symbol('(')) => {
+                self.advance();
+                let expression = self.compile_expression()?;
+                self.expect_symbol(')')?;
+                return Ok(Term::Parenthesized(Box::new(expression)));
+            }
+            Some(TokenKind::Symbol(symbol @ ('-' | '~'))) => {
+                let op = if *symbol == '-' {
+                    UnaryOp::Neg
+                } else {
+                    UnaryOp::Not
+                };
+                self.advance();
+                return Ok(Term::Unary(op, Box::new(self.compile_term()?)));
+            }
+            _ => return Err(self.error("an expression")),
+        };
+        self.advance();
+
+        Ok(term)
+    }
+
+    /// The rest of `subroutineName '(' expressionList ')'` or
+    /// `(className | varName) '.' subroutineName '(' expressionList ')'`
+    /// once its first identifier has been read.
+    fn compile_subroutine_call(&mut self, first: Identifier) -> ParseResult<SubroutineCall> {
+        let (receiver, name) = if self.is_symbol('.') {
+            self.advance();
+            (Some(first), self.expect_name("a subroutine name")?)
+        } else {
+            (None, first)
+        };
+
+        self.expect_symbol('(')?;
+        let arguments = self.compile_expression_list()?;
+        self.expect_symbol(')')?;
+
+        Ok(SubroutineCall {
+            receiver,
+            name,
+            arguments,
+        })
+    }
+
+    /// `(expression (',' expression)*)?`
+    fn compile_expression_list(&mut self) -> ParseResult<Vec<Expression>> {
+        let mut expressions = Vec::new();
+        if self.is_symbol(')') {
+            return Ok(expressions);
+        }
+
+        loop {
+            expressions.push(self.compile_expression()?);
+
+            if !self.is_symbol(',') {
+                return Ok(expressions);
+            }
+            self.advance();
+        }
+    }
+}
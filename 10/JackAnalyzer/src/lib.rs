@@ -0,0 +1,39 @@
+pub mod ast;
+pub mod compilation_engine;
+pub mod tokenizer;
+pub mod xml;
+
+pub use compilation_engine::CompilationEngine;
+pub use tokenizer::{JackTokenizer, Token};
+
+use hack_core::diagnostics::Diagnostics;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Tokenizes and parses one `.jack` file, keeping the tokens for
+/// [`xml::tokens_xml`].
+pub fn parse(source: &str, file_name: &str) -> Result<(Vec<Token>, ast::Class), Diagnostics> {
+    let tokens = JackTokenizer::new(source, file_name).tokenize()?;
+    let class = CompilationEngine::new(&tokens, source, file_name).compile_class()?;
+
+    Ok((tokens, class))
+}
+
+/// The files to process for `input_path`: the file itself, or every file
+/// with `extension` in the directory in name order.
+pub fn collect_input_filepaths(
+    input_path: &Path,
+    extension: &str,
+) -> std::io::Result<Vec<PathBuf>> {
+    if !input_path.is_dir() {
+        return Ok(vec![input_path.to_path_buf()]);
+    }
+
+    let mut input_filepaths: Vec<PathBuf> = fs::read_dir(input_path)?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == extension))
+        .collect();
+    input_filepaths.sort();
+
+    Ok(input_filepaths)
+}
@@ -0,0 +1,58 @@
+use jack_analyzer::{collect_input_filepaths, xml};
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::process;
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    if args.len() != 2 && args.len() != 3 {
+        println!("Usage: cargo run -- <input file or directory> [output directory]");
+        return;
+    }
+
+    let input_filepaths =
+        collect_input_filepaths(Path::new(&args[1]), "jack").expect("input files");
+
+    // Writing elsewhere keeps the reference XxxT.xml/Xxx.xml files intact
+    let output_dir: Option<&Path> = args.get(2).map(Path::new);
+    if let Some(output_dir) = output_dir {
+        fs::create_dir_all(output_dir).expect("output directory");
+    }
+
+    let mut error_count = 0;
+    for input_filepath in &input_filepaths {
+        let file_name = input_filepath.to_string_lossy().to_string();
+        let source = fs::read_to_string(input_filepath).expect("read file");
+
+        let (tokens, class) = match jack_analyzer::parse(&source, &file_name) {
+            Ok(parsed) => parsed,
+            Err(diagnostics) => {
+                eprintln!("{}", diagnostics);
+                error_count += diagnostics.error_count();
+                continue;
+            }
+        };
+
+        let stem = input_filepath
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let dir = output_dir
+            .or(input_filepath.parent())
+            .unwrap_or(Path::new("."));
+
+        fs::write(dir.join(format!("{}T.xml", stem)), xml::tokens_xml(&tokens))
+            .expect("write token file");
+        fs::write(dir.join(format!("{}.xml", stem)), xml::class_xml(&class))
+            .expect("write parse tree file");
+    }
+
+    if error_count > 0 {
+        eprintln!(
+            "error: could not analyze '{}' due to {} previous error(s)",
+            args[1], error_count
+        );
+        process::exit(1);
+    }
+}
@@ -0,0 +1,328 @@
+use hack_core::diagnostics::{Diagnostic, Diagnostics};
+use std::fmt;
+
+const SYMBOLS: &str = "{}()[].,;+-*/&|<>=~";
+const MAX_INTEGER: u32 = 0x7FFF;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Keyword {
+    Class,
+    Constructor,
+    Function,
+    Method,
+    Field,
+    Static,
+    Var,
+    Int,
+    Char,
+    Boolean,
+    Void,
+    True,
+    False,
+    Null,
+    This,
+    Let,
+    Do,
+    If,
+    Else,
+    While,
+    Return,
+}
+
+impl Keyword {
+    const ALL: [Keyword; 21] = [
+        Keyword::Class,
+        Keyword::Constructor,
+        Keyword::Function,
+        Keyword::Method,
+        Keyword::Field,
+        Keyword::Static,
+        Keyword::Var,
+        Keyword::Int,
+        Keyword::Char,
+        Keyword::Boolean,
+        Keyword::Void,
+        Keyword::True,
+        Keyword::False,
+        Keyword::Null,
+        Keyword::This,
+        Keyword::Let,
+        Keyword::Do,
+        Keyword::If,
+        Keyword::Else,
+        Keyword::While,
+        Keyword::Return,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Keyword::Class => "class",
+            Keyword::Constructor => "constructor",
+            Keyword::Function => "function",
+            Keyword::Method => "method",
+            Keyword::Field => "field",
+            Keyword::Static => "static",
+            Keyword::Var => "var",
+            Keyword::Int => "int",
+            Keyword::Char => "char",
+            Keyword::Boolean => "boolean",
+            Keyword::Void => "void",
+            Keyword::True => "true",
+            Keyword::False => "false",
+            Keyword::Null => "null",
+            Keyword::This => "this",
+            Keyword::Let => "let",
+            Keyword::Do => "do",
+            Keyword::If => "if",
+            Keyword::Else => "else",
+            Keyword::While => "while",
+            Keyword::Return => "return",
+        }
+    }
+
+    fn from_word(word: &str) -> Option<Keyword> {
+        Keyword::ALL
+            .into_iter()
+            .find(|keyword| keyword.as_str() == word)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TokenKind {
+    Keyword(Keyword),
+    Symbol(char),
+    IntegerConstant(u16),
+    StringConstant(String),
+    Identifier(String),
+}
+
+impl fmt::Display for TokenKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TokenKind::Keyword(keyword) => write!(f, "{}", keyword.as_str()),
+            TokenKind::Symbol(symbol) => write!(f, "{}", symbol),
+            TokenKind::IntegerConstant(value) => write!(f, "{}", value),
+            TokenKind::StringConstant(text) => write!(f, "\"{}\"", text),
+            TokenKind::Identifier(name) => write!(f, "{}", name),
+        }
+    }
+}
+
+/// A token and where it starts; `line` and `column` are 1-based.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Token {
+    pub kind: TokenKind,
+    pub line: usize,
+    pub column: usize,
+}
+
+#[derive(Debug)]
+pub enum TokenizerError {
+    UnexpectedCharacter(char),
+    UnterminatedString,
+    UnterminatedComment,
+    IntegerOutOfRange(String),
+}
+
+impl fmt::Display for TokenizerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TokenizerError::UnexpectedCharacter(c) => write!(f, "Unexpected character '{}'", c),
+            TokenizerError::UnterminatedString => {
+                write!(f, "String constant is missing its closing '\"'")
+            }
+            TokenizerError::UnterminatedComment => {
+                write!(f, "Comment is missing its closing '*/'")
+            }
+            TokenizerError::IntegerOutOfRange(digits) => write!(
+                f,
+                "Integer constant '{}' is out of range (0..{})",
+                digits, MAX_INTEGER
+            ),
+        }
+    }
+}
+
+/// Splits Jack source into tokens, skipping whitespace, `//` comments and
+/// `/* */` / `/** */` comments.
+pub struct JackTokenizer<'a> {
+    file_name: String,
+    lines: Vec<&'a str>,
+    chars: Vec<char>,
+    position: usize,
+    line: usize,
+    line_start: usize,
+    diagnostics: Diagnostics,
+}
+
+impl<'a> JackTokenizer<'a> {
+    pub fn new(source: &'a str, file_name: &str) -> Self {
+        Self {
+            file_name: file_name.to_string(),
+            lines: source.lines().collect(),
+            chars: source.chars().collect(),
+            position: 0,
+            line: 1,
+            line_start: 0,
+            diagnostics: Diagnostics::new(),
+        }
+    }
+
+    /// All tokens of the source, or every error found in it.
+    pub fn tokenize(mut self) -> Result<Vec<Token>, Diagnostics> {
+        let mut tokens: Vec<Token> = Vec::new();
+
+        while let Some(token) = self.advance() {
+            tokens.push(token);
+        }
+
+        if self.diagnostics.is_empty() {
+            Ok(tokens)
+        } else {
+            Err(self.diagnostics)
+        }
+    }
+
+    fn peek(&self, offset: usize) -> Option<char> {
+        self.chars.get(self.position + offset).copied()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek(0)?;
+        self.position += 1;
+        if c == '\n' {
+            self.line += 1;
+            self.line_start = self.position;
+        }
+
+        Some(c)
+    }
+
+    fn column(&self, position: usize) -> usize {
+        position - self.line_start + 1
+    }
+
+    fn error(&mut self, line: usize, column: usize, text: &str, e: TokenizerError) {
+        let source_line = self.lines.get(line - 1).copied().unwrap_or("");
+        let mut diagnostic =
+            Diagnostic::new(&self.file_name, line, source_line, text, e.to_string());
+        diagnostic.column = column;
+        self.diagnostics.push(diagnostic);
+    }
+
+    /// Skips whitespace and comments; false once the source is exhausted.
+    fn skip_trivia(&mut self) -> bool {
+        loop {
+            match (self.peek(0), self.peek(1)) {
+                (None, _) => return false,
+                (Some(c), _) if c.is_whitespace() => {
+                    self.bump();
+                }
+                (Some('/'), Some('/')) => {
+                    while self.peek(0).is_some_and(|c| c != '\n') {
+                        self.bump();
+                    }
+                }
+                (Some('/'), Some('*')) => {
+                    let (line, column) = (self.line, self.column(self.position));
+                    self.bump();
+                    self.bump();
+
+                    loop {
+                        match (self.peek(0), self.peek(1)) {
+                            (Some('*'), Some('/')) => {
+                                self.bump();
+                                self.bump();
+                                break;
+                            }
+                            (Some(_), _) => {
+                                self.bump();
+                            }
+                            (None, _) => {
+                                self.error(line, column, "/*", TokenizerError::UnterminatedComment);
+                                return false;
+                            }
+                        }
+                    }
+                }
+                _ => return true,
+            }
+        }
+    }
+
+    /// The next token, or `None` at the end of the source. Bad input is
+    /// reported and skipped so the rest of the file still gets checked.
+    fn advance(&mut self) -> Option<Token> {
+        loop {
+            if !self.skip_trivia() {
+                return None;
+            }
+
+            let (line, start) = (self.line, self.position);
+            let column = self.column(start);
+            let c = self.bump()?;
+
+            let kind = if SYMBOLS.contains(c) {
+                TokenKind::Symbol(c)
+            } else if c == '"' {
+                let mut text = String::new();
+                loop {
+                    match self.peek(0) {
+                        Some('"') => {
+                            self.bump();
+                            break;
+                        }
+                        Some(c) if c != '\n' => {
+                            text.push(c);
+                            self.bump();
+                        }
+                        _ => {
+                            self.error(line, column, "\"", TokenizerError::UnterminatedString);
+                            break;
+                        }
+                    }
+                }
+                TokenKind::StringConstant(text)
+            } else if c.is_ascii_digit() {
+                let mut digits = c.to_string();
+                while let Some(c) = self.peek(0).filter(char::is_ascii_digit) {
+                    digits.push(c);
+                    self.bump();
+                }
+
+                match digits.parse::<u32>() {
+                    Ok(value) if value <= MAX_INTEGER => TokenKind::IntegerConstant(value as u16),
+                    _ => {
+                        let e = TokenizerError::IntegerOutOfRange(digits.clone());
+                        self.error(line, column, &digits, e);
+                        continue;
+                    }
+                }
+            } else if c.is_ascii_alphabetic() || c == '_' {
+                let mut word = c.to_string();
+                while let Some(c) = self
+                    .peek(0)
+                    .filter(|c| c.is_ascii_alphanumeric() || *c == '_')
+                {
+                    word.push(c);
+                    self.bump();
+                }
+
+                match Keyword::from_word(&word) {
+                    Some(keyword) => TokenKind::Keyword(keyword),
+                    None => TokenKind::Identifier(word),
+                }
+            } else {
+                self.error(
+                    line,
+                    column,
+                    &c.to_string(),
+                    TokenizerError::UnexpectedCharacter(c),
+                );
+                continue;
+            };
+
+            return Some(Token { kind, line, column });
+        }
+    }
+}
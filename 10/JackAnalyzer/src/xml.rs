@@ -0,0 +1,321 @@
+use crate::ast::{
+    Class, ClassVarDec, ClassVarKind, Expression, KeywordConstant, Parameter, Statement,
+    StatementKind, SubroutineBody, SubroutineCall, SubroutineDec, SubroutineKind, Term, Type,
+    VarDec,
+};
+use crate::tokenizer::{Keyword, Token, TokenKind};
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn token_element(kind: &TokenKind) -> (&'static str, String) {
+    match kind {
+        TokenKind::Keyword(keyword) => ("keyword", keyword.as_str().to_string()),
+        TokenKind::Symbol(symbol) => ("symbol", symbol.to_string()),
+        TokenKind::IntegerConstant(value) => ("integerConstant", value.to_string()),
+        TokenKind::StringConstant(text) => ("stringConstant", text.clone()),
+        TokenKind::Identifier(name) => ("identifier", name.clone()),
+    }
+}
+
+/// The token list in the `XxxT.xml` format of the reference tools.
+pub fn tokens_xml(tokens: &[Token]) -> String {
+    let mut xml = String::from("<tokens>\n");
+    for token in tokens {
+        let (tag, text) = token_element(&token.kind);
+        xml.push_str(&format!("<{0}> {1} </{0}>\n", tag, escape(&text)));
+    }
+    xml.push_str("</tokens>\n");
+
+    xml
+}
+
+/// The parse tree in the `Xxx.xml` format of the reference tools: one
+/// element per grammar rule, tokens as leaves, two spaces per level.
+pub fn class_xml(class: &Class) -> String {
+    let mut writer = XmlWriter::default();
+    writer.class(class);
+
+    writer.xml
+}
+
+#[derive(Default)]
+struct XmlWriter {
+    xml: String,
+    depth: usize,
+}
+
+impl XmlWriter {
+    fn open(&mut self, tag: &str) {
+        self.xml
+            .push_str(&format!("{}<{}>\n", "  ".repeat(self.depth), tag));
+        self.depth += 1;
+    }
+
+    fn close(&mut self, tag: &str) {
+        self.depth -= 1;
+        self.xml
+            .push_str(&format!("{}</{}>\n", "  ".repeat(self.depth), tag));
+    }
+
+    fn token(&mut self, kind: TokenKind) {
+        let (tag, text) = token_element(&kind);
+        self.xml.push_str(&format!(
+            "{}<{1}> {2} </{1}>\n",
+            "  ".repeat(self.depth),
+            tag,
+            escape(&text)
+        ));
+    }
+
+    fn keyword(&mut self, keyword: Keyword) {
+        self.token(TokenKind::Keyword(keyword));
+    }
+
+    fn symbol(&mut self, symbol: char) {
+        self.token(TokenKind::Symbol(symbol));
+    }
+
+    fn identifier(&mut self, name: &str) {
+        self.token(TokenKind::Identifier(name.to_string()));
+    }
+
+    fn var_type(&mut self, var_type: &Type) {
+        match var_type {
+            Type::Int => self.keyword(Keyword::Int),
+            Type::Char => self.keyword(Keyword::Char),
+            Type::Boolean => self.keyword(Keyword::Boolean),
+            Type::Class(name) => self.identifier(name),
+        }
+    }
+
+    /// `name (',' name)* ';'`
+    fn names(&mut self, names: &[String]) {
+        for (i, name) in names.iter().enumerate() {
+            if i > 0 {
+                self.symbol(',');
+            }
+            self.identifier(name);
+        }
+        self.symbol(';');
+    }
+
+    fn class(&mut self, class: &Class) {
+        self.open("class");
+        self.keyword(Keyword::Class);
+        self.identifier(&class.name);
+        self.symbol('{');
+        for class_var_dec in &class.class_var_decs {
+            self.class_var_dec(class_var_dec);
+        }
+        for subroutine_dec in &class.subroutine_decs {
+            self.subroutine_dec(subroutine_dec);
+        }
+        self.symbol('}');
+        self.close("class");
+    }
+
+    fn class_var_dec(&mut self, class_var_dec: &ClassVarDec) {
+        self.open("classVarDec");
+        self.keyword(match class_var_dec.kind {
+            ClassVarKind::Static => Keyword::Static,
+            ClassVarKind::Field => Keyword::Field,
+        });
+        self.var_type(&class_var_dec.var_type);
+        self.names(&class_var_dec.names);
+        self.close("classVarDec");
+    }
+
+    fn subroutine_dec(&mut self, subroutine_dec: &SubroutineDec) {
+        self.open("subroutineDec");
+        self.keyword(match subroutine_dec.kind {
+            SubroutineKind::Constructor => Keyword::Constructor,
+            SubroutineKind::Function => Keyword::Function,
+            SubroutineKind::Method => Keyword::Method,
+        });
+        match &subroutine_dec.return_type {
+            Some(return_type) => self.var_type(return_type),
+            None => self.keyword(Keyword::Void),
+        }
+        self.identifier(&subroutine_dec.name);
+        self.symbol('(');
+        self.parameter_list(&subroutine_dec.parameters);
+        self.symbol(')');
+        self.subroutine_body(&subroutine_dec.body);
+        self.close("subroutineDec");
+    }
+
+    fn parameter_list(&mut self, parameters: &[Parameter]) {
+        self.open("parameterList");
+        for (i, parameter) in parameters.iter().enumerate() {
+            if i > 0 {
+                self.symbol(',');
+            }
+            self.var_type(&parameter.var_type);
+            self.identifier(&parameter.name);
+        }
+        self.close("parameterList");
+    }
+
+    fn subroutine_body(&mut self, body: &SubroutineBody) {
+        self.open("subroutineBody");
+        self.symbol('{');
+        for var_dec in &body.var_decs {
+            self.var_dec(var_dec);
+        }
+        self.statements(&body.statements);
+        self.symbol('}');
+        self.close("subroutineBody");
+    }
+
+    fn var_dec(&mut self, var_dec: &VarDec) {
+        self.open("varDec");
+        self.keyword(Keyword::Var);
+        self.var_type(&var_dec.var_type);
+        self.names(&var_dec.names);
+        self.close("varDec");
+    }
+
+    fn statements(&mut self, statements: &[Statement]) {
+        self.open("statements");
+        for statement in statements {
+            self.statement(statement);
+        }
+        self.close("statements");
+    }
+
+    /// `'{' statements '}'`
+    fn block(&mut self, statements: &[Statement]) {
+        self.symbol('{');
+        self.statements(statements);
+        self.symbol('}');
+    }
+
+    /// `'(' expression ')'`
+    fn parenthesized(&mut self, expression: &Expression) {
+        self.symbol('(');
+        self.expression(expression);
+        self.symbol(')');
+    }
+
+    fn statement(&mut self, statement: &Statement) {
+        match &statement.kind {
+            StatementKind::Let { name, index, value } => {
+                self.open("letStatement");
+                self.keyword(Keyword::Let);
+                self.identifier(&name.name);
+                if let Some(index) = index {
+                    self.symbol('[');
+                    self.expression(index);
+                    self.symbol(']');
+                }
+                self.symbol('=');
+                self.expression(value);
+                self.symbol(';');
+                self.close("letStatement");
+            }
+            StatementKind::If {
+                condition,
+                then_statements,
+                else_statements,
+            } => {
+                self.open("ifStatement");
+                self.keyword(Keyword::If);
+                self.parenthesized(condition);
+                self.block(then_statements);
+                if let Some(else_statements) = else_statements {
+                    self.keyword(Keyword::Else);
+                    self.block(else_statements);
+                }
+                self.close("ifStatement");
+            }
+            StatementKind::While {
+                condition,
+                statements,
+            } => {
+                self.open("whileStatement");
+                self.keyword(Keyword::While);
+                self.parenthesized(condition);
+                self.block(statements);
+                self.close("whileStatement");
+            }
+            StatementKind::Do(call) => {
+                self.open("doStatement");
+                self.keyword(Keyword::Do);
+                self.subroutine_call(call);
+                self.symbol(';');
+                self.close("doStatement");
+            }
+            StatementKind::Return(value) => {
+                self.open("returnStatement");
+                self.keyword(Keyword::Return);
+                if let Some(value) = value {
+                    self.expression(value);
+                }
+                self.symbol(';');
+                self.close("returnStatement");
+            }
+        }
+    }
+
+    fn expression(&mut self, expression: &Expression) {
+        self.open("expression");
+        self.term(&expression.term);
+        for (op, term) in &expression.operations {
+            self.symbol(op.symbol());
+            self.term(term);
+        }
+        self.close("expression");
+    }
+
+    fn term(&mut self, term: &Term) {
+        self.open("term");
+        match term {
+            Term::IntegerConstant(value) => self.token(TokenKind::IntegerConstant(*value)),
+            Term::StringConstant(text) => self.token(TokenKind::StringConstant(text.clone())),
+            Term::KeywordConstant(constant, _) => self.keyword(match constant {
+                KeywordConstant::True => Keyword::True,
+                KeywordConstant::False => Keyword::False,
+                KeywordConstant::Null => Keyword::Null,
+                KeywordConstant::This => Keyword::This,
+            }),
+            Term::Variable(name) => self.identifier(&name.name),
+            Term::ArrayElement(name, index) => {
+                self.identifier(&name.name);
+                self.symbol('[');
+                self.expression(index);
+                self.symbol(']');
+            }
+            Term::Call(call) => self.subroutine_call(call),
+            Term::Parenthesized(expression) => self.parenthesized(expression),
+            Term::Unary(op, operand) => {
+                self.symbol(op.symbol());
+                self.term(operand);
+            }
+        }
+        self.close("term");
+    }
+
+    /// Written as bare tokens, without an element of its own
+    fn subroutine_call(&mut self, call: &SubroutineCall) {
+        if let Some(receiver) = &call.receiver {
+            self.identifier(&receiver.name);
+            self.symbol('.');
+        }
+        self.identifier(&call.name.name);
+        self.symbol('(');
+        self.open("expressionList");
+        for (i, argument) in call.arguments.iter().enumerate() {
+            if i > 0 {
+                self.symbol(',');
+            }
+            self.expression(argument);
+        }
+        self.close("expressionList");
+        self.symbol(')');
+    }
+}
@@ -0,0 +1,30 @@
+use jack_analyzer::xml::{class_xml, tokens_xml};
+
+const SOURCE: &str = include_str!("Statements/Main.jack");
+
+/// `Statements/Main.jack` uses every statement kind, symbols that need
+/// escaping and subroutines with no parameters or arguments; its `.xml`
+/// files are in the reference tools' format.
+#[test]
+fn tokens_match_the_reference_format() {
+    let (tokens, _) = jack_analyzer::parse(SOURCE, "Main.jack").expect("parse");
+
+    assert_eq!(tokens_xml(&tokens), include_str!("Statements/MainT.xml"));
+}
+
+#[test]
+fn parse_tree_matches_the_reference_format() {
+    let (_, class) = jack_analyzer::parse(SOURCE, "Main.jack").expect("parse");
+
+    assert_eq!(class_xml(&class), include_str!("Statements/Main.xml"));
+}
+
+#[test]
+fn syntax_errors_point_at_the_unexpected_token() {
+    let source = "class Main {\n    function void main() {\n        let x = ;\n    }\n}\n";
+    let diagnostics = jack_analyzer::parse(source, "Main.jack").expect_err("syntax error");
+    let diagnostic = diagnostics.iter().next().expect("one error");
+
+    assert_eq!((diagnostic.line, diagnostic.column), (3, 17));
+    assert_eq!(diagnostic.message, "Expected an expression but found ';'");
+}
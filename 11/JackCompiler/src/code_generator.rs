@@ -0,0 +1,378 @@
+use crate::symbol_table::{Kind, SymbolTable};
+use crate::vm_writer::{Arithmetic, Segment, VmWriter};
+use hack_core::diagnostics::{Diagnostic, Diagnostics};
+use jack_analyzer::ast::{
+    BinaryOp, Class, ClassVarKind, Expression, Identifier, KeywordConstant, Position, Statement,
+    StatementKind, SubroutineCall, SubroutineDec, SubroutineKind, Term, Type, UnaryOp,
+};
+use std::fmt;
+
+#[derive(Debug)]
+pub enum CompilerError {
+    UndefinedVariable(String),
+    NotAnObject(String),
+    MethodCallFromFunction(String),
+    FieldInFunction(String),
+    ThisInFunction,
+}
+
+impl fmt::Display for CompilerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CompilerError::UndefinedVariable(name) => write!(f, "Undefined variable '{}'", name),
+            CompilerError::NotAnObject(name) => write!(
+                f,
+                "Cannot call a method on '{}', which is not an object",
+                name
+            ),
+            CompilerError::MethodCallFromFunction(name) => write!(
+                f,
+                "Cannot call method '{}' from a function, which has no 'this'",
+                name
+            ),
+            CompilerError::FieldInFunction(name) => write!(
+                f,
+                "Cannot use field '{}' in a function, which has no 'this'",
+                name
+            ),
+            CompilerError::ThisInFunction => write!(f, "'this' is not available in a function"),
+        }
+    }
+}
+
+/// Translates one parsed Jack class into VM code. Errors are reported at
+/// the name they are about, and all of them are collected before giving up.
+pub struct CodeGenerator<'a> {
+    file_name: String,
+    lines: Vec<&'a str>,
+    class_name: String,
+    symbols: SymbolTable,
+    writer: VmWriter,
+    subroutine_kind: SubroutineKind,
+    label_count: usize,
+    diagnostics: Diagnostics,
+}
+
+impl<'a> CodeGenerator<'a> {
+    pub fn new(source: &'a str, file_name: &str) -> Self {
+        Self {
+            file_name: file_name.to_string(),
+            lines: source.lines().collect(),
+            class_name: String::new(),
+            symbols: SymbolTable::new(),
+            writer: VmWriter::new(),
+            subroutine_kind: SubroutineKind::Function,
+            label_count: 0,
+            diagnostics: Diagnostics::new(),
+        }
+    }
+
+    fn error(&mut self, position: Position, text: &str, e: CompilerError) {
+        let source_line = self.lines.get(position.line - 1).copied().unwrap_or("");
+        let mut diagnostic = Diagnostic::new(
+            &self.file_name,
+            position.line,
+            source_line,
+            text,
+            e.to_string(),
+        );
+        diagnostic.column = position.column;
+        self.diagnostics.push(diagnostic);
+    }
+
+    /// A pair of labels unique within the current subroutine.
+    fn labels(&mut self, first: &str, second: &str) -> (String, String) {
+        let n = self.label_count;
+        self.label_count += 1;
+
+        (format!("{}{}", first, n), format!("{}{}", second, n))
+    }
+
+    /// The VM code for `class`, or every error found in it.
+    pub fn compile_class(mut self, class: &Class) -> Result<String, Diagnostics> {
+        self.class_name = class.name.clone();
+
+        for class_var_dec in &class.class_var_decs {
+            let kind = match class_var_dec.kind {
+                ClassVarKind::Static => Kind::Static,
+                ClassVarKind::Field => Kind::Field,
+            };
+            for name in &class_var_dec.names {
+                self.symbols.define(name, &class_var_dec.var_type, kind);
+            }
+        }
+
+        for subroutine_dec in &class.subroutine_decs {
+            self.compile_subroutine(subroutine_dec);
+        }
+
+        if self.diagnostics.is_empty() {
+            Ok(self.writer.into_output())
+        } else {
+            Err(self.diagnostics)
+        }
+    }
+
+    fn compile_subroutine(&mut self, subroutine_dec: &SubroutineDec) {
+        self.symbols.start_subroutine();
+        self.subroutine_kind = subroutine_dec.kind;
+        self.label_count = 0;
+
+        // The object a method is called on is its hidden first argument
+        if subroutine_dec.kind == SubroutineKind::Method {
+            let class_type = Type::Class(self.class_name.clone());
+            self.symbols.define("this", &class_type, Kind::Argument);
+        }
+        for parameter in &subroutine_dec.parameters {
+            self.symbols
+                .define(&parameter.name, &parameter.var_type, Kind::Argument);
+        }
+        for var_dec in &subroutine_dec.body.var_decs {
+            for name in &var_dec.names {
+                self.symbols.define(name, &var_dec.var_type, Kind::Local);
+            }
+        }
+
+        let name = format!("{}.{}", self.class_name, subroutine_dec.name);
+        self.writer
+            .write_function(&name, self.symbols.var_count(Kind::Local));
+
+        match subroutine_dec.kind {
+            SubroutineKind::Constructor => {
+                let size = self.symbols.var_count(Kind::Field);
+                self.writer.write_push(Segment::Constant, size);
+                self.writer.write_call("Memory.alloc", 1);
+                self.writer.write_pop(Segment::Pointer, 0);
+            }
+            SubroutineKind::Method => {
+                self.writer.write_push(Segment::Argument, 0);
+                self.writer.write_pop(Segment::Pointer, 0);
+            }
+            SubroutineKind::Function => {}
+        }
+
+        self.compile_statements(&subroutine_dec.body.statements);
+    }
+
+    fn compile_statements(&mut self, statements: &[Statement]) {
+        for statement in statements {
+            self.compile_statement(&statement.kind);
+        }
+    }
+
+    fn compile_statement(&mut self, statement: &StatementKind) {
+        match statement {
+            StatementKind::Let {
+                name,
+                index: None,
+                value,
+            } => {
+                self.compile_expression(value);
+                if let Some((segment, index)) = self.variable(name) {
+                    self.writer.write_pop(segment, index);
+                }
+            }
+            StatementKind::Let {
+                name,
+                index: Some(index),
+                value,
+            } => {
+                // The value may itself index an array, so the target address
+                // waits in temp 0 until the value is known
+                self.compile_element_address(name, index);
+                self.compile_expression(value);
+                self.writer.write_pop(Segment::Temp, 0);
+                self.writer.write_pop(Segment::Pointer, 1);
+                self.writer.write_push(Segment::Temp, 0);
+                self.writer.write_pop(Segment::That, 0);
+            }
+            StatementKind::If {
+                condition,
+                then_statements,
+                else_statements,
+            } => {
+                let (else_label, end_label) = self.labels("IF_ELSE", "IF_END");
+
+                self.compile_expression(condition);
+                self.writer.write_arithmetic(Arithmetic::Not);
+                self.writer.write_if(&else_label);
+                self.compile_statements(then_statements);
+                match else_statements {
+                    Some(else_statements) => {
+                        self.writer.write_goto(&end_label);
+                        self.writer.write_label(&else_label);
+                        self.compile_statements(else_statements);
+                        self.writer.write_label(&end_label);
+                    }
+                    None => self.writer.write_label(&else_label),
+                }
+            }
+            StatementKind::While {
+                condition,
+                statements,
+            } => {
+                let (exp_label, end_label) = self.labels("WHILE_EXP", "WHILE_END");
+
+                self.writer.write_label(&exp_label);
+                self.compile_expression(condition);
+                self.writer.write_arithmetic(Arithmetic::Not);
+                self.writer.write_if(&end_label);
+                self.compile_statements(statements);
+                self.writer.write_goto(&exp_label);
+                self.writer.write_label(&end_label);
+            }
+            StatementKind::Do(call) => {
+                self.compile_subroutine_call(call);
+                self.writer.write_pop(Segment::Temp, 0);
+            }
+            StatementKind::Return(value) => {
+                match value {
+                    Some(value) => self.compile_expression(value),
+                    // Every call returns a value; void ones return 0
+                    None => self.writer.write_push(Segment::Constant, 0),
+                }
+                self.writer.write_return();
+            }
+        }
+    }
+
+    /// Where a variable lives, or `None` after reporting it as undefined.
+    fn variable(&mut self, name: &Identifier) -> Option<(Segment, u16)> {
+        match self.symbols.lookup(&name.name) {
+            Some(symbol) => {
+                let (kind, index) = (symbol.kind, symbol.index);
+                self.check_field_access(name, kind);
+                Some((kind.segment(), index))
+            }
+            None => {
+                let e = CompilerError::UndefinedVariable(name.name.clone());
+                self.error(name.position, &name.name, e);
+                None
+            }
+        }
+    }
+
+    /// Fields live in `this`, which functions don't set up.
+    fn check_field_access(&mut self, name: &Identifier, kind: Kind) {
+        if kind == Kind::Field && self.subroutine_kind == SubroutineKind::Function {
+            let e = CompilerError::FieldInFunction(name.name.clone());
+            self.error(name.position, &name.name, e);
+        }
+    }
+
+    fn compile_variable(&mut self, name: &Identifier) {
+        if let Some((segment, index)) = self.variable(name) {
+            self.writer.write_push(segment, index);
+        }
+    }
+
+    /// Pushes the address of `name[index]`.
+    fn compile_element_address(&mut self, name: &Identifier, index: &Expression) {
+        self.compile_variable(name);
+        self.compile_expression(index);
+        self.writer.write_arithmetic(Arithmetic::Add);
+    }
+
+    fn compile_expression(&mut self, expression: &Expression) {
+        self.compile_term(&expression.term);
+        for (op, term) in &expression.operations {
+            self.compile_term(term);
+            match op {
+                BinaryOp::Add => self.writer.write_arithmetic(Arithmetic::Add),
+                BinaryOp::Sub => self.writer.write_arithmetic(Arithmetic::Sub),
+                BinaryOp::Mul => self.writer.write_call("Math.multiply", 2),
+                BinaryOp::Div => self.writer.write_call("Math.divide", 2),
+                BinaryOp::And => self.writer.write_arithmetic(Arithmetic::And),
+                BinaryOp::Or => self.writer.write_arithmetic(Arithmetic::Or),
+                BinaryOp::Lt => self.writer.write_arithmetic(Arithmetic::Lt),
+                BinaryOp::Gt => self.writer.write_arithmetic(Arithmetic::Gt),
+                BinaryOp::Eq => self.writer.write_arithmetic(Arithmetic::Eq),
+            }
+        }
+    }
+
+    fn compile_term(&mut self, term: &Term) {
+        match term {
+            Term::IntegerConstant(value) => self.writer.write_push(Segment::Constant, *value),
+            Term::StringConstant(text) => {
+                self.writer
+                    .write_push(Segment::Constant, text.chars().count() as u16);
+                self.writer.write_call("String.new", 1);
+                for c in text.chars() {
+                    self.writer.write_push(Segment::Constant, c as u16);
+                    self.writer.write_call("String.appendChar", 2);
+                }
+            }
+            Term::KeywordConstant(constant, position) => match constant {
+                // true is -1, all bits set
+                KeywordConstant::True => {
+                    self.writer.write_push(Segment::Constant, 0);
+                    self.writer.write_arithmetic(Arithmetic::Not);
+                }
+                KeywordConstant::False | KeywordConstant::Null => {
+                    self.writer.write_push(Segment::Constant, 0)
+                }
+                KeywordConstant::This => {
+                    if self.subroutine_kind == SubroutineKind::Function {
+                        self.error(*position, "this", CompilerError::ThisInFunction);
+                    }
+                    self.writer.write_push(Segment::Pointer, 0);
+                }
+            },
+            Term::Variable(name) => self.compile_variable(name),
+            Term::ArrayElement(name, index) => {
+                self.compile_element_address(name, index);
+                self.writer.write_pop(Segment::Pointer, 1);
+                self.writer.write_push(Segment::That, 0);
+            }
+            Term::Call(call) => self.compile_subroutine_call(call),
+            Term::Parenthesized(expression) => self.compile_expression(expression),
+            Term::Unary(op, operand) => {
+                self.compile_term(operand);
+                self.writer.write_arithmetic(match op {
+                    UnaryOp::Neg => Arithmetic::Neg,
+                    UnaryOp::Not => Arithmetic::Not,
+                });
+            }
+        }
+    }
+
+    /// `name(...)` is a method of this class, `variable.name(...)` a method
+    /// of the variable's class, and anything else a function or
+    /// constructor. Methods get the object pushed as an extra first
+    /// argument.
+    fn compile_subroutine_call(&mut self, call: &SubroutineCall) {
+        let (function_name, object_count) = match &call.receiver {
+            None => {
+                if self.subroutine_kind == SubroutineKind::Function {
+                    let e = CompilerError::MethodCallFromFunction(call.name.name.clone());
+                    self.error(call.name.position, &call.name.name, e);
+                }
+                self.writer.write_push(Segment::Pointer, 0);
+                (format!("{}.{}", self.class_name, call.name.name), 1)
+            }
+            Some(receiver) => match self.symbols.lookup(&receiver.name).cloned() {
+                Some(symbol) => {
+                    self.check_field_access(receiver, symbol.kind);
+                    let class_name = match symbol.var_type {
+                        Type::Class(class_name) => class_name,
+                        _ => {
+                            let e = CompilerError::NotAnObject(receiver.name.clone());
+                            self.error(receiver.position, &receiver.name, e);
+                            receiver.name.clone()
+                        }
+                    };
+                    self.writer.write_push(symbol.kind.segment(), symbol.index);
+                    (format!("{}.{}", class_name, call.name.name), 1)
+                }
+                None => (format!("{}.{}", receiver.name, call.name.name), 0),
+            },
+        };
+
+        for argument in &call.arguments {
+            self.compile_expression(argument);
+        }
+        self.writer
+            .write_call(&function_name, object_count + call.arguments.len());
+    }
+}
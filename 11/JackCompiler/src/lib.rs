@@ -0,0 +1,14 @@
+pub mod code_generator;
+pub mod symbol_table;
+pub mod vm_writer;
+
+pub use code_generator::CodeGenerator;
+
+use hack_core::diagnostics::Diagnostics;
+
+/// Compiles one `.jack` file into the contents of its `.vm` file.
+pub fn compile(source: &str, file_name: &str) -> Result<String, Diagnostics> {
+    let (_, class) = jack_analyzer::parse(source, file_name)?;
+
+    CodeGenerator::new(source, file_name).compile_class(&class)
+}
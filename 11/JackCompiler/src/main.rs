@@ -0,0 +1,41 @@
+use jack_analyzer::collect_input_filepaths;
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::process;
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    if args.len() != 2 {
+        println!("Usage: cargo run -- <input file or directory>");
+        return;
+    }
+
+    let input_filepaths =
+        collect_input_filepaths(Path::new(&args[1]), "jack").expect("input files");
+
+    let mut error_count = 0;
+    for input_filepath in &input_filepaths {
+        let file_name = input_filepath.to_string_lossy().to_string();
+        let source = fs::read_to_string(input_filepath).expect("read file");
+
+        match jack_compiler::compile(&source, &file_name) {
+            // Each Xxx.jack becomes Xxx.vm next to it, ready for the VM translator
+            Ok(vm_code) => {
+                fs::write(input_filepath.with_extension("vm"), vm_code).expect("write vm file")
+            }
+            Err(diagnostics) => {
+                eprintln!("{}", diagnostics);
+                error_count += diagnostics.error_count();
+            }
+        }
+    }
+
+    if error_count > 0 {
+        eprintln!(
+            "error: could not compile '{}' due to {} previous error(s)",
+            args[1], error_count
+        );
+        process::exit(1);
+    }
+}
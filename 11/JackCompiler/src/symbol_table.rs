@@ -0,0 +1,80 @@
+use crate::vm_writer::Segment;
+use jack_analyzer::ast::Type;
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Kind {
+    Static,
+    Field,
+    Argument,
+    Local,
+}
+
+impl Kind {
+    pub fn segment(&self) -> Segment {
+        match self {
+            Kind::Static => Segment::Static,
+            Kind::Field => Segment::This,
+            Kind::Argument => Segment::Argument,
+            Kind::Local => Segment::Local,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Symbol {
+    pub var_type: Type,
+    pub kind: Kind,
+    pub index: u16,
+}
+
+/// Class scope (statics and fields) and subroutine scope (arguments and
+/// locals); subroutine names shadow class names.
+#[derive(Debug, Default)]
+pub struct SymbolTable {
+    class_scope: HashMap<String, Symbol>,
+    subroutine_scope: HashMap<String, Symbol>,
+    counts: HashMap<Kind, u16>,
+}
+
+impl SymbolTable {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Forgets the previous subroutine's arguments and locals.
+    pub fn start_subroutine(&mut self) {
+        self.subroutine_scope.clear();
+        self.counts.remove(&Kind::Argument);
+        self.counts.remove(&Kind::Local);
+    }
+
+    /// Gives `name` the next index of its kind.
+    pub fn define(&mut self, name: &str, var_type: &Type, kind: Kind) {
+        let index = self.var_count(kind);
+        self.counts.insert(kind, index + 1);
+
+        let scope = match kind {
+            Kind::Static | Kind::Field => &mut self.class_scope,
+            Kind::Argument | Kind::Local => &mut self.subroutine_scope,
+        };
+        scope.insert(
+            name.to_string(),
+            Symbol {
+                var_type: var_type.clone(),
+                kind,
+                index,
+            },
+        );
+    }
+
+    pub fn var_count(&self, kind: Kind) -> u16 {
+        self.counts.get(&kind).copied().unwrap_or(0)
+    }
+
+    pub fn lookup(&self, name: &str) -> Option<&Symbol> {
+        self.subroutine_scope
+            .get(name)
+            .or_else(|| self.class_scope.get(name))
+    }
+}
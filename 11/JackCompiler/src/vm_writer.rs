@@ -0,0 +1,116 @@
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Segment {
+    Constant,
+    Argument,
+    Local,
+    Static,
+    This,
+    That,
+    Pointer,
+    Temp,
+}
+
+impl fmt::Display for Segment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Segment::Constant => "constant",
+            Segment::Argument => "argument",
+            Segment::Local => "local",
+            Segment::Static => "static",
+            Segment::This => "this",
+            Segment::That => "that",
+            Segment::Pointer => "pointer",
+            Segment::Temp => "temp",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Arithmetic {
+    Add,
+    Sub,
+    Neg,
+    Eq,
+    Gt,
+    Lt,
+    And,
+    Or,
+    Not,
+}
+
+impl fmt::Display for Arithmetic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let command = match self {
+            Arithmetic::Add => "add",
+            Arithmetic::Sub => "sub",
+            Arithmetic::Neg => "neg",
+            Arithmetic::Eq => "eq",
+            Arithmetic::Gt => "gt",
+            Arithmetic::Lt => "lt",
+            Arithmetic::And => "and",
+            Arithmetic::Or => "or",
+            Arithmetic::Not => "not",
+        };
+        write!(f, "{}", command)
+    }
+}
+
+/// Writes VM commands, one per line, in the format `08/VMTranslatorII` reads.
+#[derive(Debug, Default)]
+pub struct VmWriter {
+    output: String,
+}
+
+impl VmWriter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn write(&mut self, command: String) {
+        self.output.push_str(&command);
+        self.output.push('\n');
+    }
+
+    pub fn write_push(&mut self, segment: Segment, index: u16) {
+        self.write(format!("push {} {}", segment, index));
+    }
+
+    pub fn write_pop(&mut self, segment: Segment, index: u16) {
+        self.write(format!("pop {} {}", segment, index));
+    }
+
+    pub fn write_arithmetic(&mut self, command: Arithmetic) {
+        self.write(command.to_string());
+    }
+
+    pub fn write_label(&mut self, label: &str) {
+        self.write(format!("label {}", label));
+    }
+
+    pub fn write_goto(&mut self, label: &str) {
+        self.write(format!("goto {}", label));
+    }
+
+    pub fn write_if(&mut self, label: &str) {
+        self.write(format!("if-goto {}", label));
+    }
+
+    pub fn write_call(&mut self, name: &str, n_args: usize) {
+        self.write(format!("call {} {}", name, n_args));
+    }
+
+    pub fn write_function(&mut self, name: &str, n_locals: u16) {
+        self.write(format!("function {} {}", name, n_locals));
+    }
+
+    pub fn write_return(&mut self) {
+        self.write("return".to_string());
+    }
+
+    pub fn into_output(self) -> String {
+        self.output
+    }
+}
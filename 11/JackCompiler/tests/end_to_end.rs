@@ -0,0 +1,111 @@
+use cpu_emulator::{Computer, RunOutcome};
+use std::fs;
+use std::path::{Path, PathBuf};
+use vm_translator::code_writer::CodeWriter;
+use vm_translator::emulator::{self, VmEmulator};
+use vm_translator::parser::Parser;
+
+const MAX_VM_STEPS: u64 = 100_000;
+const MAX_CPU_CYCLES: u64 = 1_000_000;
+
+const CLASSES: [(&str, &str); 4] = [
+    ("Main", include_str!("Objects/Main.jack")),
+    ("Point", include_str!("Objects/Point.jack")),
+    ("Memory", include_str!("Objects/Memory.jack")),
+    ("Array", include_str!("Objects/Array.jack")),
+];
+
+/// What `Objects/Main.jack` leaves in RAM: its results, then the two
+/// points and arrays the bump allocator handed out from 2048.
+const EXPECTED_RAM: [(usize, i16); 9] = [
+    (3000, 7),
+    (3001, 9),
+    (3002, 14),
+    // p = Point.new(3, 4)
+    (2048, 3),
+    (2049, 4),
+    // b[1] = 9, then a[2] = b[1]
+    (2052, 9),
+    (2054, 9),
+    // p.scaled(2), allocated last
+    (2056, 6),
+    (2057, 8),
+];
+
+/// Compiles every class into `dir` next to `Objects/Sys.vm`, returning the
+/// `.vm` files in name order.
+fn compile_into(dir: &Path) -> Vec<String> {
+    fs::create_dir_all(dir).expect("scratch directory");
+
+    let mut files: Vec<PathBuf> = CLASSES
+        .iter()
+        .map(|(name, source)| {
+            let file_name = format!("{}.jack", name);
+            let vm_code = jack_compiler::compile(source, &file_name)
+                .unwrap_or_else(|diagnostics| panic!("{}", diagnostics));
+            let path = dir.join(format!("{}.vm", name));
+            fs::write(&path, vm_code).expect("write .vm file");
+            path
+        })
+        .collect();
+
+    let sys = dir.join("Sys.vm");
+    fs::write(&sys, include_str!("Objects/Sys.vm")).expect("write Sys.vm");
+    files.push(sys);
+    files.sort();
+
+    files
+        .iter()
+        .map(|path| path.to_string_lossy().to_string())
+        .collect()
+}
+
+fn scratch_dir(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("jack-end-to-end-{}-{}", std::process::id(), name))
+}
+
+#[test]
+fn compiled_program_runs_on_the_vm_emulator() {
+    let dir = scratch_dir("vm");
+    let mut parser = Parser::without_writer(compile_into(&dir));
+    let commands = parser.commands().expect("read .vm files");
+    fs::remove_dir_all(&dir).ok();
+    assert!(parser.diagnostics().is_empty(), "{}", parser.diagnostics());
+
+    let mut vm = VmEmulator::new(commands).expect("load program");
+    vm.bootstrap().expect("call Sys.init");
+    assert!(matches!(
+        vm.run(MAX_VM_STEPS),
+        Ok(emulator::RunOutcome::Halted { .. })
+    ));
+
+    for (address, value) in EXPECTED_RAM {
+        assert_eq!(vm.peek(address) as i16, value, "RAM[{}]", address);
+    }
+}
+
+#[test]
+fn compiled_program_runs_on_the_cpu() {
+    let dir = scratch_dir("cpu");
+    let input_filepaths = compile_into(&dir);
+    let output = dir.join("Objects.asm");
+
+    let writer = CodeWriter::new(output.to_string_lossy().to_string());
+    let mut parser = Parser::new(input_filepaths, writer);
+    parser.parse(true).expect("translate");
+    assert!(parser.diagnostics().is_empty(), "{}", parser.diagnostics());
+
+    let asm = fs::read_to_string(&output).expect("read .asm file");
+    fs::remove_dir_all(&dir).ok();
+    let words = hack_asm::assemble(&asm).expect("assemble").words;
+
+    let mut computer = Computer::new(&words).expect("load program");
+    assert!(matches!(
+        computer.run(MAX_CPU_CYCLES),
+        RunOutcome::Halted { .. }
+    ));
+
+    for (address, value) in EXPECTED_RAM {
+        assert_eq!(computer.peek(address) as i16, value, "RAM[{}]", address);
+    }
+}
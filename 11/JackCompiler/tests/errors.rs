@@ -0,0 +1,76 @@
+/// A class with a field `size` and a method `grow()`; `BODY` is replaced
+/// with the statements of `function void run()`.
+const CLASS: &str = "\
+class Main {
+    field int size;
+
+    function void run() {
+        var int n;
+BODY        return;
+    }
+
+    method void grow() {
+        return;
+    }
+}
+";
+
+/// The line, column and message of each error in `body`.
+fn errors(body: &str) -> Vec<(usize, usize, String)> {
+    let source = CLASS.replace("BODY", body);
+    let diagnostics = jack_compiler::compile(&source, "Main.jack").expect_err("semantic error");
+
+    diagnostics
+        .iter()
+        .map(|diagnostic| {
+            (
+                diagnostic.line,
+                diagnostic.column,
+                diagnostic.message.clone(),
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn undefined_variable() {
+    assert_eq!(
+        errors("        let n = i;\n"),
+        vec![(6, 17, "Undefined variable 'i'".to_string())]
+    );
+}
+
+#[test]
+fn field_in_a_function() {
+    assert_eq!(
+        errors("        let n = 1 + size;\n"),
+        vec![(
+            6,
+            21,
+            "Cannot use field 'size' in a function, which has no 'this'".to_string()
+        )]
+    );
+}
+
+#[test]
+fn method_call_from_a_function() {
+    assert_eq!(
+        errors("        do grow();\n"),
+        vec![(
+            6,
+            12,
+            "Cannot call method 'grow' from a function, which has no 'this'".to_string()
+        )]
+    );
+}
+
+#[test]
+fn this_in_a_function() {
+    assert_eq!(
+        errors("        let n = thisCount + this;\n"),
+        vec![
+            (6, 17, "Undefined variable 'thisCount'".to_string()),
+            (6, 29, "'this' is not available in a function".to_string()),
+        ]
+    );
+}
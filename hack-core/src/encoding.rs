@@ -0,0 +1,75 @@
+/// Largest value an A-instruction can load (15 bits).
+pub const MAX_ADDRESS: usize = 0x7FFF;
+
+/// `a` bit followed by `c1..c6` for each comp mnemonic. Commutative spellings
+/// (A+D, M|D, ...) are accepted alongside the canonical forms, which are
+/// always listed first.
+pub const COMP_TABLE: [(&str, &str); 34] = [
+    ("0", "0101010"),
+    ("1", "0111111"),
+    ("-1", "0111010"),
+    ("D", "0001100"),
+    ("A", "0110000"),
+    ("!D", "0001101"),
+    ("!A", "0110001"),
+    ("-D", "0001111"),
+    ("-A", "0110011"),
+    ("D+1", "0011111"),
+    ("A+1", "0110111"),
+    ("D-1", "0001110"),
+    ("A-1", "0110010"),
+    ("D+A", "0000010"),
+    ("A+D", "0000010"),
+    ("D-A", "0010011"),
+    ("A-D", "0000111"),
+    ("D&A", "0000000"),
+    ("A&D", "0000000"),
+    ("D|A", "0010101"),
+    ("A|D", "0010101"),
+    ("M", "1110000"),
+    ("!M", "1110001"),
+    ("-M", "1110011"),
+    ("M+1", "1110111"),
+    ("M-1", "1110010"),
+    ("D+M", "1000010"),
+    ("M+D", "1000010"),
+    ("D-M", "1010011"),
+    ("M-D", "1000111"),
+    ("D&M", "1000000"),
+    ("M&D", "1000000"),
+    ("D|M", "1010101"),
+    ("M|D", "1010101"),
+];
+
+/// `d1..d3` bits for each dest mnemonic. Reordered spellings (DM, ADM, ...)
+/// are accepted after the canonical forms, as the official assembler does.
+pub const DEST_TABLE: [(&str, &str); 16] = [
+    ("null", "000"),
+    ("M", "001"),
+    ("D", "010"),
+    ("MD", "011"),
+    ("A", "100"),
+    ("AM", "101"),
+    ("AD", "110"),
+    ("AMD", "111"),
+    ("DM", "011"),
+    ("MA", "101"),
+    ("DA", "110"),
+    ("ADM", "111"),
+    ("MAD", "111"),
+    ("MDA", "111"),
+    ("DAM", "111"),
+    ("DMA", "111"),
+];
+
+/// `j1..j3` bits for each jump mnemonic.
+pub const JUMP_TABLE: [(&str, &str); 8] = [
+    ("null", "000"),
+    ("JGT", "001"),
+    ("JEQ", "010"),
+    ("JGE", "011"),
+    ("JLT", "100"),
+    ("JNE", "101"),
+    ("JLE", "110"),
+    ("JMP", "111"),
+];
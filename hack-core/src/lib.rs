@@ -0,0 +1,3 @@
+pub mod diagnostics;
+pub mod encoding;
+pub mod symbols;
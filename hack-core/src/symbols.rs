@@ -0,0 +1,25 @@
+use std::collections::HashMap;
+
+/// First RAM address handed out to variables by the assembler.
+pub const STARTING_VARIABLE_ADDRESS: usize = 16;
+
+/// Symbols every Hack program can use without defining them.
+pub fn predefined_symbols() -> HashMap<String, usize> {
+    let mut default_symbols = HashMap::<String, usize>::from([
+        ("SP".to_string(), 0x0000),
+        ("LCL".to_string(), 0x0001),
+        ("ARG".to_string(), 0x0002),
+        ("THIS".to_string(), 0x0003),
+        ("THAT".to_string(), 0x0004),
+        ("SCREEN".to_string(), 0x4000),
+        ("KBD".to_string(), 0x6000),
+    ]);
+
+    // Default registers R0..R15
+    for i in 0..=15 {
+        let formatted_symbol = format!("R{:}", i);
+        default_symbols.insert(formatted_symbol, i);
+    }
+
+    default_symbols
+}
@@ -0,0 +1,37 @@
+use hack_core::diagnostics::Diagnostic;
+use std::fmt;
+
+#[derive(Debug)]
+pub enum HdlError {
+    Io(String, std::io::Error),
+    Diagnostic(Box<Diagnostic>),
+    UnknownChip(String),
+    UnknownPin(String),
+    ValueTooWide(String, String),
+    InvalidAssignment(String),
+}
+
+impl fmt::Display for HdlError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HdlError::Io(path, e) => write!(f, "{}: {}", path, e),
+            HdlError::Diagnostic(diagnostic) => write!(f, "{}", diagnostic),
+            HdlError::UnknownChip(name) => write!(f, "Chip '{}' was not found", name),
+            HdlError::UnknownPin(name) => write!(f, "Unknown pin '{}'", name),
+            HdlError::ValueTooWide(name, value) => {
+                write!(f, "Value {} does not fit in pin '{}'", value, name)
+            }
+            HdlError::InvalidAssignment(assignment) => write!(
+                f,
+                "Invalid input '{}', expected pin=value with a value from -32768 to 65535",
+                assignment
+            ),
+        }
+    }
+}
+
+impl From<Diagnostic> for HdlError {
+    fn from(diagnostic: Diagnostic) -> Self {
+        HdlError::Diagnostic(Box::new(diagnostic))
+    }
+}
@@ -0,0 +1,8 @@
+pub mod error;
+pub mod library;
+pub mod parser;
+pub mod simulator;
+
+pub use error::HdlError;
+pub use library::ChipLibrary;
+pub use simulator::ChipSimulator;
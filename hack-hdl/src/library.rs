@@ -0,0 +1,138 @@
+use crate::error::HdlError;
+use crate::parser::{Body, Chip, PinDecl, parse_chip};
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::rc::Rc;
+
+/// Chips the simulator implements natively. `Nand` and `DFF` are the
+/// primitives everything else is built from; the rest are the memory and
+/// I/O devices of project 05 that have no HDL implementation, plus the RAM
+/// chips of project 03, which are only used when no `.hdl` file is found
+/// (a gate-level RAM16K is millions of gates).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Builtin {
+    Nand,
+    Dff,
+    Register,
+    /// Writable memory with the given number of address bits (RAMn, Screen)
+    Ram(usize),
+    Keyboard,
+    Rom32K,
+}
+
+fn pins(decls: &[(&str, usize)]) -> Vec<PinDecl> {
+    decls
+        .iter()
+        .map(|(name, width)| PinDecl {
+            name: name.to_string(),
+            width: *width,
+        })
+        .collect()
+}
+
+impl Builtin {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "Nand" => Some(Builtin::Nand),
+            "DFF" => Some(Builtin::Dff),
+            "ARegister" | "DRegister" => Some(Builtin::Register),
+            "RAM8" => Some(Builtin::Ram(3)),
+            "RAM64" => Some(Builtin::Ram(6)),
+            "RAM512" => Some(Builtin::Ram(9)),
+            "RAM4K" => Some(Builtin::Ram(12)),
+            "RAM16K" => Some(Builtin::Ram(14)),
+            "Screen" => Some(Builtin::Ram(13)),
+            "Keyboard" => Some(Builtin::Keyboard),
+            "ROM32K" => Some(Builtin::Rom32K),
+            _ => None,
+        }
+    }
+
+    pub fn inputs(&self) -> Vec<PinDecl> {
+        match self {
+            Builtin::Nand => pins(&[("a", 1), ("b", 1)]),
+            Builtin::Dff => pins(&[("in", 1)]),
+            Builtin::Register => pins(&[("in", 16), ("load", 1)]),
+            Builtin::Ram(bits) => pins(&[("in", 16), ("load", 1), ("address", *bits)]),
+            Builtin::Keyboard => Vec::new(),
+            Builtin::Rom32K => pins(&[("address", 15)]),
+        }
+    }
+
+    pub fn outputs(&self) -> Vec<PinDecl> {
+        match self {
+            Builtin::Nand | Builtin::Dff => pins(&[("out", 1)]),
+            _ => pins(&[("out", 16)]),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum Resolved {
+    Chip(Rc<Chip>),
+    Builtin(Builtin),
+}
+
+/// Finds chips by name: first as `Name.hdl` in the search directories, in
+/// order, then among the built-in chips. Parsed chips are cached.
+pub struct ChipLibrary {
+    dirs: Vec<PathBuf>,
+    cache: HashMap<String, Resolved>,
+}
+
+impl ChipLibrary {
+    pub fn new(dirs: Vec<PathBuf>) -> Self {
+        Self {
+            dirs,
+            cache: HashMap::new(),
+        }
+    }
+
+    /// Uses the built-in implementation of a chip even if an `.hdl` file
+    /// for it exists.
+    pub fn use_builtin(&mut self, name: &str) -> Result<(), HdlError> {
+        let builtin =
+            Builtin::from_name(name).ok_or_else(|| HdlError::UnknownChip(name.to_string()))?;
+        self.cache
+            .insert(name.to_string(), Resolved::Builtin(builtin));
+
+        Ok(())
+    }
+
+    pub fn resolve(&mut self, name: &str) -> Result<Resolved, HdlError> {
+        if let Some(resolved) = self.cache.get(name) {
+            return Ok(resolved.clone());
+        }
+
+        let path = self
+            .dirs
+            .iter()
+            .map(|dir| dir.join(format!("{}.hdl", name)))
+            .find(|path| path.is_file());
+
+        let resolved = match path {
+            Some(path) => {
+                let display = path.display().to_string();
+                let source =
+                    fs::read_to_string(&path).map_err(|e| HdlError::Io(display.clone(), e))?;
+                let chip = parse_chip(&display, &source)?;
+
+                match &chip.body {
+                    Body::Builtin(builtin) => Resolved::Builtin(
+                        Builtin::from_name(builtin)
+                            .ok_or_else(|| HdlError::UnknownChip(builtin.clone()))?,
+                    ),
+                    Body::Parts(_) => Resolved::Chip(Rc::new(chip)),
+                }
+            }
+            None => Resolved::Builtin(
+                Builtin::from_name(name).ok_or_else(|| HdlError::UnknownChip(name.to_string()))?,
+            ),
+        };
+
+        self.cache.insert(name.to_string(), resolved.clone());
+
+        Ok(resolved)
+    }
+}
@@ -0,0 +1,117 @@
+use hack_hdl::{ChipLibrary, ChipSimulator, HdlError};
+use std::env;
+use std::path::{Path, PathBuf};
+use std::process;
+
+struct Options {
+    lib_dirs: Vec<PathBuf>,
+    builtins: Vec<String>,
+    assignments: Vec<String>,
+    clock: bool,
+}
+
+fn run(chip_path: &Path, options: Options) -> Result<(), HdlError> {
+    let chip_dir = chip_path
+        .parent()
+        .filter(|dir| !dir.as_os_str().is_empty())
+        .unwrap_or(Path::new("."))
+        .to_path_buf();
+    let chip_name = chip_path
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .unwrap_or_default()
+        .to_string();
+
+    let mut dirs = vec![chip_dir];
+    dirs.extend(options.lib_dirs);
+    let mut library = ChipLibrary::new(dirs);
+    for name in &options.builtins {
+        library.use_builtin(name)?;
+    }
+    let mut simulator = ChipSimulator::new(&mut library, &chip_name)?;
+
+    for assignment in &options.assignments {
+        let invalid = || HdlError::InvalidAssignment(assignment.clone());
+        let (pin, text) = assignment.split_once('=').ok_or_else(invalid)?;
+        let value = text
+            .parse::<i32>()
+            .ok()
+            .filter(|value| (-0x8000..=0xFFFF).contains(value))
+            .ok_or_else(invalid)?;
+
+        // Report the value as it was given, not as the u16 it became
+        simulator
+            .set(pin, value as u16)
+            .map_err(|e| match e {
+                HdlError::ValueTooWide(pin, _) => HdlError::ValueTooWide(pin, text.to_string()),
+                e => e,
+            })?;
+    }
+
+    simulator.eval();
+    if options.clock {
+        simulator.tick();
+        simulator.tock();
+    }
+
+    for (pin, width) in simulator
+        .input_pins()
+        .into_iter()
+        .chain(simulator.output_pins())
+    {
+        let value = simulator.get(pin)?;
+        println!(
+            "{:>10} = {:0width$b} ({})",
+            pin,
+            value,
+            value as i16,
+            width = width
+        );
+    }
+
+    Ok(())
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    if args.len() < 2 {
+        println!(
+            "Usage: cargo run -- <Chip.hdl> [--lib <dir>]... [--builtin <Chip>]... [--clock] [pin=value ...]"
+        );
+        return;
+    }
+
+    let mut options = Options {
+        lib_dirs: Vec::new(),
+        builtins: Vec::new(),
+        assignments: Vec::new(),
+        clock: false,
+    };
+    let mut rest = args[2..].iter();
+    while let Some(arg) = rest.next() {
+        match arg.as_str() {
+            "--lib" | "--builtin" => match rest.next() {
+                Some(value) if arg == "--lib" => options.lib_dirs.push(PathBuf::from(value)),
+                Some(value) => options.builtins.push(value.clone()),
+                None => {
+                    eprintln!("error: '{}' needs a value", arg);
+                    process::exit(1);
+                }
+            },
+            "--clock" => options.clock = true,
+            _ => options.assignments.push(arg.clone()),
+        }
+    }
+
+    match run(Path::new(&args[1]), options) {
+        Ok(()) => {}
+        Err(HdlError::Diagnostic(diagnostic)) => {
+            eprintln!("{}", diagnostic);
+            process::exit(1);
+        }
+        Err(e) => {
+            eprintln!("error: {}", e);
+            process::exit(1);
+        }
+    }
+}
@@ -0,0 +1,423 @@
+use hack_core::diagnostics::Diagnostic;
+
+/// A pin declared in a chip's `IN` or `OUT` section.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PinDecl {
+    pub name: String,
+    pub width: usize,
+}
+
+/// `name`, `name[i]` or `name[lo..hi]`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PinRef {
+    pub name: String,
+    pub range: Option<(usize, usize)>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Source {
+    Pin(PinRef),
+    Constant(bool),
+}
+
+/// `part_pin=chip_side` inside a part's argument list. `column` is where
+/// the part pin starts and `external_column` where the chip side does.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Connection {
+    pub part_pin: PinRef,
+    pub external: Source,
+    pub line: usize,
+    pub column: usize,
+    pub external_column: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Part {
+    pub name: String,
+    pub connections: Vec<Connection>,
+    pub line: usize,
+    pub column: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Body {
+    Parts(Vec<Part>),
+    Builtin(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Chip {
+    pub name: String,
+    pub inputs: Vec<PinDecl>,
+    pub outputs: Vec<PinDecl>,
+    pub body: Body,
+    pub file: String,
+    pub source: String,
+}
+
+impl Chip {
+    /// Builds a diagnostic pointing at `text`, which starts at `line` and
+    /// `column` of this chip's source.
+    pub fn diagnostic(&self, line: usize, column: usize, text: &str, message: String) -> Diagnostic {
+        let source_line = self
+            .source
+            .lines()
+            .nth(line.saturating_sub(1))
+            .unwrap_or("");
+        let mut diagnostic = Diagnostic::new(&self.file, line, source_line, text, message);
+        diagnostic.column = column;
+        diagnostic
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Ident(String),
+    Number(usize),
+    Symbol(&'static str),
+}
+
+impl Token {
+    fn text(&self) -> String {
+        match self {
+            Token::Ident(ident) => ident.clone(),
+            Token::Number(n) => n.to_string(),
+            Token::Symbol(symbol) => symbol.to_string(),
+        }
+    }
+}
+
+const SYMBOLS: [&str; 10] = ["..", "{", "}", "(", ")", "[", "]", ",", ";", ":"];
+
+struct Parser<'a> {
+    file: &'a str,
+    source: &'a str,
+    /// Each token with its 1-based line and column
+    tokens: Vec<(usize, usize, Token)>,
+    position: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn new(file: &'a str, source: &'a str) -> Result<Self, Diagnostic> {
+        let mut parser = Self {
+            file,
+            source,
+            tokens: Vec::new(),
+            position: 0,
+        };
+        parser.tokenize()?;
+
+        Ok(parser)
+    }
+
+    fn error(&self, line: usize, text: &str, message: String) -> Diagnostic {
+        let source_line = self
+            .source
+            .lines()
+            .nth(line.saturating_sub(1))
+            .unwrap_or("");
+        Diagnostic::new(self.file, line, source_line, text, message)
+    }
+
+    fn tokenize(&mut self) -> Result<(), Diagnostic> {
+        let chars: Vec<char> = self.source.chars().collect();
+        let mut line: usize = 1;
+        let mut line_start: usize = 0;
+        let mut i: usize = 0;
+
+        while i < chars.len() {
+            let c = chars[i];
+            let rest: String = chars[i..chars.len().min(i + 2)].iter().collect();
+
+            let column = i - line_start + 1;
+
+            if c == '\n' {
+                line += 1;
+                i += 1;
+                line_start = i;
+            } else if c.is_whitespace() {
+                i += 1;
+            } else if rest == "//" {
+                while i < chars.len() && chars[i] != '\n' {
+                    i += 1;
+                }
+            } else if rest == "/*" {
+                i += 2;
+                while i < chars.len() && !(chars[i] == '*' && chars.get(i + 1) == Some(&'/')) {
+                    if chars[i] == '\n' {
+                        line += 1;
+                        line_start = i + 1;
+                    }
+                    i += 1;
+                }
+                i += 2;
+            } else if c.is_ascii_digit() {
+                let start = i;
+                while i < chars.len() && chars[i].is_ascii_digit() {
+                    i += 1;
+                }
+                let digits: String = chars[start..i].iter().collect();
+                let number = digits
+                    .parse::<usize>()
+                    .map_err(|_| self.error(line, &digits, "Number is too large".to_string()))?;
+                self.tokens.push((line, column, Token::Number(number)));
+            } else if c.is_ascii_alphabetic() || c == '_' {
+                let start = i;
+                while i < chars.len() && (chars[i].is_ascii_alphanumeric() || chars[i] == '_') {
+                    i += 1;
+                }
+                self.tokens
+                    .push((line, column, Token::Ident(chars[start..i].iter().collect())));
+            } else if let Some(symbol) = SYMBOLS.iter().find(|symbol| rest.starts_with(**symbol)) {
+                self.tokens.push((line, column, Token::Symbol(symbol)));
+                i += symbol.len();
+            } else if c == '=' {
+                self.tokens.push((line, column, Token::Symbol("=")));
+                i += 1;
+            } else {
+                return Err(self.error(
+                    line,
+                    &c.to_string(),
+                    format!("Unexpected character '{}'", c),
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn line(&self) -> usize {
+        self.tokens
+            .get(self.position)
+            .or(self.tokens.last())
+            .map(|(line, _, _)| *line)
+            .unwrap_or(1)
+    }
+
+    fn column(&self) -> usize {
+        self.tokens
+            .get(self.position)
+            .map(|(_, column, _)| *column)
+            .unwrap_or(1)
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position).map(|(_, _, token)| token)
+    }
+
+    fn unexpected(&self, expected: &str) -> Diagnostic {
+        match self.peek() {
+            Some(token) => self.error(
+                self.line(),
+                &token.text(),
+                format!("Expected {}, found '{}'", expected, token.text()),
+            ),
+            None => self.error(
+                self.line(),
+                "",
+                format!("Expected {}, found end of file", expected),
+            ),
+        }
+    }
+
+    fn is_symbol(&self, symbol: &str) -> bool {
+        matches!(self.peek(), Some(Token::Symbol(s)) if *s == symbol)
+    }
+
+    fn is_keyword(&self, keyword: &str) -> bool {
+        matches!(self.peek(), Some(Token::Ident(ident)) if ident == keyword)
+    }
+
+    fn symbol(&mut self, symbol: &str) -> Result<(), Diagnostic> {
+        if !self.is_symbol(symbol) {
+            return Err(self.unexpected(&format!("'{}'", symbol)));
+        }
+        self.position += 1;
+
+        Ok(())
+    }
+
+    fn keyword(&mut self, keyword: &str) -> Result<(), Diagnostic> {
+        if !self.is_keyword(keyword) {
+            return Err(self.unexpected(keyword));
+        }
+        self.position += 1;
+
+        Ok(())
+    }
+
+    fn ident(&mut self) -> Result<String, Diagnostic> {
+        match self.peek() {
+            Some(Token::Ident(ident)) => {
+                let ident = ident.clone();
+                self.position += 1;
+                Ok(ident)
+            }
+            _ => Err(self.unexpected("a name")),
+        }
+    }
+
+    fn number(&mut self) -> Result<usize, Diagnostic> {
+        match self.peek() {
+            Some(Token::Number(n)) => {
+                let n = *n;
+                self.position += 1;
+                Ok(n)
+            }
+            _ => Err(self.unexpected("a number")),
+        }
+    }
+
+    fn pin_decls(&mut self) -> Result<Vec<PinDecl>, Diagnostic> {
+        let mut pins: Vec<PinDecl> = Vec::new();
+        loop {
+            let name = self.ident()?;
+            let width = if self.is_symbol("[") {
+                self.symbol("[")?;
+                let width = self.number()?;
+                self.symbol("]")?;
+                width
+            } else {
+                1
+            };
+            pins.push(PinDecl { name, width });
+
+            if self.is_symbol(";") {
+                self.symbol(";")?;
+                return Ok(pins);
+            }
+            self.symbol(",")?;
+        }
+    }
+
+    fn pin_ref(&mut self) -> Result<PinRef, Diagnostic> {
+        let name = self.ident()?;
+        let range = if self.is_symbol("[") {
+            self.symbol("[")?;
+            let lo = self.number()?;
+            let hi = if self.is_symbol("..") {
+                self.symbol("..")?;
+                self.number()?
+            } else {
+                lo
+            };
+            self.symbol("]")?;
+            Some((lo, hi))
+        } else {
+            None
+        };
+
+        Ok(PinRef { name, range })
+    }
+
+    fn part(&mut self) -> Result<Part, Diagnostic> {
+        let (line, column) = (self.line(), self.column());
+        let name = self.ident()?;
+        let mut connections: Vec<Connection> = Vec::new();
+
+        self.symbol("(")?;
+        loop {
+            let (line, column) = (self.line(), self.column());
+            let part_pin = self.pin_ref()?;
+            self.symbol("=")?;
+            let external_column = self.column();
+            let external = match self.peek() {
+                Some(Token::Ident(ident)) if ident == "true" || ident == "false" => {
+                    let value = ident == "true";
+                    self.position += 1;
+                    Source::Constant(value)
+                }
+                _ => Source::Pin(self.pin_ref()?),
+            };
+            connections.push(Connection {
+                part_pin,
+                external,
+                line,
+                column,
+                external_column,
+            });
+
+            if self.is_symbol(")") {
+                break;
+            }
+            self.symbol(",")?;
+        }
+        self.symbol(")")?;
+        self.symbol(";")?;
+
+        Ok(Part {
+            name,
+            connections,
+            line,
+            column,
+        })
+    }
+
+    fn chip(&mut self) -> Result<Chip, Diagnostic> {
+        self.keyword("CHIP")?;
+        let name = self.ident()?;
+        self.symbol("{")?;
+
+        let inputs = if self.is_keyword("IN") {
+            self.keyword("IN")?;
+            self.pin_decls()?
+        } else {
+            Vec::new()
+        };
+        let outputs = if self.is_keyword("OUT") {
+            self.keyword("OUT")?;
+            self.pin_decls()?
+        } else {
+            Vec::new()
+        };
+
+        let body = if self.is_keyword("BUILTIN") {
+            self.keyword("BUILTIN")?;
+            let builtin = self.ident()?;
+            self.symbol(";")?;
+
+            // The clocked pin list only matters to the official simulator
+            if self.is_keyword("CLOCKED") {
+                self.keyword("CLOCKED")?;
+                while !self.is_symbol(";") {
+                    self.ident()?;
+                    if self.is_symbol(",") {
+                        self.symbol(",")?;
+                    }
+                }
+                self.symbol(";")?;
+            }
+            Body::Builtin(builtin)
+        } else {
+            self.keyword("PARTS")?;
+            self.symbol(":")?;
+            let mut parts: Vec<Part> = Vec::new();
+            while !self.is_symbol("}") {
+                if self.peek().is_none() {
+                    return Err(self.unexpected("'}'"));
+                }
+                parts.push(self.part()?);
+            }
+            Body::Parts(parts)
+        };
+
+        self.symbol("}")?;
+        if self.peek().is_some() {
+            return Err(self.unexpected("end of file"));
+        }
+
+        Ok(Chip {
+            name,
+            inputs,
+            outputs,
+            body,
+            file: self.file.to_string(),
+            source: self.source.to_string(),
+        })
+    }
+}
+
+/// Parses one `.hdl` file. `file` is only used in diagnostics.
+pub fn parse_chip(file: &str, source: &str) -> Result<Chip, Diagnostic> {
+    Parser::new(file, source)?.chip()
+}
@@ -0,0 +1,910 @@
+use crate::error::HdlError;
+use crate::library::{Builtin, ChipLibrary, Resolved};
+use crate::parser::{Body, Chip, Connection, PinDecl, PinRef, Source};
+use std::collections::{HashMap, VecDeque};
+use std::rc::Rc;
+
+type Net = usize;
+
+const FALSE_NET: Net = 0;
+const TRUE_NET: Net = 1;
+const MAX_PIN_WIDTH: usize = 16;
+
+#[derive(Debug, Clone)]
+enum Component {
+    Nand {
+        a: Net,
+        b: Net,
+        out: Net,
+    },
+    Dff {
+        input: Net,
+        output: Net,
+        state: bool,
+        next: bool,
+    },
+    Register {
+        input: Vec<Net>,
+        load: Net,
+        output: Vec<Net>,
+        state: u16,
+        next: u16,
+    },
+    /// RAMn and Screen (writable) and ROM32K (read-only): `out` follows `address`
+    /// combinationally, writes land on the clock.
+    Memory {
+        input: Vec<Net>,
+        load: Net,
+        address: Vec<Net>,
+        output: Vec<Net>,
+        data: Vec<u16>,
+        pending: Option<(usize, u16)>,
+        rom: bool,
+    },
+    Keyboard {
+        output: Vec<Net>,
+    },
+}
+
+impl Component {
+    /// Inputs whose value reaches an output within the same time step.
+    fn combinational_inputs(&self) -> Vec<Net> {
+        match self {
+            Component::Nand { a, b, .. } => vec![*a, *b],
+            Component::Memory { address, .. } => address.clone(),
+            _ => Vec::new(),
+        }
+    }
+
+    fn outputs(&self) -> Vec<Net> {
+        match self {
+            Component::Nand { out, .. } => vec![*out],
+            Component::Dff { output, .. } => vec![*output],
+            Component::Register { output, .. }
+            | Component::Memory { output, .. }
+            | Component::Keyboard { output } => output.clone(),
+        }
+    }
+
+    fn nets_mut(&mut self) -> Vec<&mut Net> {
+        match self {
+            Component::Nand { a, b, out } => vec![a, b, out],
+            Component::Dff { input, output, .. } => vec![input, output],
+            Component::Register {
+                input,
+                load,
+                output,
+                ..
+            } => input
+                .iter_mut()
+                .chain(output.iter_mut())
+                .chain(std::iter::once(load))
+                .collect(),
+            Component::Memory {
+                input,
+                load,
+                address,
+                output,
+                ..
+            } => input
+                .iter_mut()
+                .chain(address.iter_mut())
+                .chain(output.iter_mut())
+                .chain(std::iter::once(load))
+                .collect(),
+            Component::Keyboard { output } => output.iter_mut().collect(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Direction {
+    In,
+    Out,
+}
+
+/// The nets behind each pin of one chip instance.
+#[derive(Debug, Default)]
+struct Interface {
+    inputs: Vec<(String, Vec<Net>)>,
+    outputs: Vec<(String, Vec<Net>)>,
+}
+
+impl Interface {
+    fn pin(&self, name: &str) -> Option<(Direction, &Vec<Net>)> {
+        let find = |pins: &'_ [(String, Vec<Net>)]| pins.iter().position(|(pin, _)| pin == name);
+
+        if let Some(i) = find(&self.inputs) {
+            return Some((Direction::In, &self.inputs[i].1));
+        }
+        find(&self.outputs).map(|i| (Direction::Out, &self.outputs[i].1))
+    }
+}
+
+/// One part instance in the flattened hierarchy: `parts[part]` of `chip`,
+/// inside the instance `parent`.
+struct Origin {
+    parent: Option<usize>,
+    chip: Rc<Chip>,
+    part: usize,
+}
+
+/// Flattens a chip hierarchy into primitive components over single-bit nets.
+/// Connections are recorded by merging nets (union-find); a merged net may
+/// have at most one driver.
+struct Builder<'a> {
+    library: &'a mut ChipLibrary,
+    parent: Vec<Net>,
+    driven: Vec<bool>,
+    components: Vec<Component>,
+    stack: Vec<String>,
+    /// Part instances, and the one each component came from
+    origins: Vec<Origin>,
+    origin: Option<usize>,
+    component_origins: Vec<Option<usize>>,
+}
+
+impl Builder<'_> {
+    fn new_net(&mut self, driven: bool) -> Net {
+        self.parent.push(self.parent.len());
+        self.driven.push(driven);
+        self.parent.len() - 1
+    }
+
+    fn new_nets(&mut self, width: usize, driven: bool) -> Vec<Net> {
+        (0..width).map(|_| self.new_net(driven)).collect()
+    }
+
+    fn find(&mut self, net: Net) -> Net {
+        let mut root = net;
+        while self.parent[root] != root {
+            root = self.parent[root];
+        }
+
+        // Path compression
+        let mut current = net;
+        while self.parent[current] != root {
+            let next = self.parent[current];
+            self.parent[current] = root;
+            current = next;
+        }
+
+        root
+    }
+
+    /// Merges two nets; fails if both already have a driver.
+    fn alias(&mut self, a: Net, b: Net) -> Result<(), ()> {
+        let (a, b) = (self.find(a), self.find(b));
+        if a == b {
+            return Ok(());
+        }
+        if self.driven[a] && self.driven[b] {
+            return Err(());
+        }
+
+        // Keep the constant nets as roots so they stay recognisable
+        let (root, child) = if b <= TRUE_NET { (b, a) } else { (a, b) };
+        self.parent[child] = root;
+        self.driven[root] = self.driven[a] || self.driven[b];
+
+        Ok(())
+    }
+
+    fn instantiate_builtin(&mut self, builtin: Builtin) -> Interface {
+        let mut interface = Interface::default();
+        for pin in builtin.inputs() {
+            let nets = self.new_nets(pin.width, false);
+            interface.inputs.push((pin.name, nets));
+        }
+        for pin in builtin.outputs() {
+            let nets = self.new_nets(pin.width, true);
+            interface.outputs.push((pin.name, nets));
+        }
+
+        let input = |name: &str| {
+            interface
+                .pin(name)
+                .map(|(_, nets)| nets.clone())
+                .unwrap_or_default()
+        };
+        let output = input("out");
+
+        let component = match builtin {
+            Builtin::Nand => Component::Nand {
+                a: input("a")[0],
+                b: input("b")[0],
+                out: output[0],
+            },
+            Builtin::Dff => Component::Dff {
+                input: input("in")[0],
+                output: output[0],
+                state: false,
+                next: false,
+            },
+            Builtin::Register => Component::Register {
+                input: input("in"),
+                load: input("load")[0],
+                output,
+                state: 0,
+                next: 0,
+            },
+            Builtin::Ram(bits) => Component::Memory {
+                input: input("in"),
+                load: input("load")[0],
+                address: input("address"),
+                output,
+                data: vec![0; 1 << bits],
+                pending: None,
+                rom: false,
+            },
+            Builtin::Rom32K => Component::Memory {
+                input: Vec::new(),
+                load: FALSE_NET,
+                address: input("address"),
+                output,
+                data: vec![0; 1 << 15],
+                pending: None,
+                rom: true,
+            },
+            Builtin::Keyboard => Component::Keyboard { output },
+        };
+        self.components.push(component);
+        self.component_origins.push(self.origin);
+
+        interface
+    }
+
+    fn instantiate(
+        &mut self,
+        name: &str,
+    ) -> Result<(Interface, HashMap<String, Vec<Net>>), HdlError> {
+        match self.library.resolve(name)? {
+            Resolved::Builtin(builtin) => Ok((self.instantiate_builtin(builtin), HashMap::new())),
+            Resolved::Chip(chip) => {
+                if self.stack.iter().any(|parent| parent == name) {
+                    return Err(HdlError::UnknownChip(format!(
+                        "{} (it contains itself via {})",
+                        name,
+                        self.stack.join(" -> ")
+                    )));
+                }
+
+                self.stack.push(name.to_string());
+                let result = self.instantiate_chip(&chip);
+                self.stack.pop();
+
+                result
+            }
+        }
+    }
+
+    fn slice(
+        &self,
+        chip: &Chip,
+        connection: &Connection,
+        column: usize,
+        pin: &PinRef,
+        nets: &[Net],
+    ) -> Result<Vec<Net>, HdlError> {
+        let Some((lo, hi)) = pin.range else {
+            return Ok(nets.to_vec());
+        };
+
+        if lo > hi || hi >= nets.len() {
+            return Err(chip
+                .diagnostic(
+                    connection.line,
+                    column,
+                    &pin.name,
+                    format!(
+                        "Sub-bus {}[{}..{}] is out of range for a {}-bit pin",
+                        pin.name,
+                        lo,
+                        hi,
+                        nets.len()
+                    ),
+                )
+                .into());
+        }
+
+        Ok(nets[lo..=hi].to_vec())
+    }
+
+    fn connect(
+        &mut self,
+        chip: &Chip,
+        connection: &Connection,
+        part_nets: &[Net],
+        external_nets: &[Net],
+    ) -> Result<(), HdlError> {
+        let text = match &connection.external {
+            Source::Pin(external) => &external.name,
+            Source::Constant(true) => "true",
+            Source::Constant(false) => "false",
+        };
+
+        if part_nets.len() != external_nets.len() {
+            return Err(chip
+                .diagnostic(
+                    connection.line,
+                    connection.external_column,
+                    text,
+                    format!(
+                        "Width mismatch: {} bit(s) connected to {} bit(s)",
+                        part_nets.len(),
+                        external_nets.len()
+                    ),
+                )
+                .into());
+        }
+
+        for (part_net, external_net) in part_nets.iter().zip(external_nets) {
+            if self.alias(*part_net, *external_net).is_err() {
+                return Err(chip
+                    .diagnostic(
+                        connection.line,
+                        connection.external_column,
+                        text,
+                        format!("Pin '{}' has more than one source", text),
+                    )
+                    .into());
+            }
+        }
+
+        Ok(())
+    }
+
+    fn instantiate_chip(
+        &mut self,
+        chip: &Rc<Chip>,
+    ) -> Result<(Interface, HashMap<String, Vec<Net>>), HdlError> {
+        let Body::Parts(parts) = &chip.body else {
+            unreachable!("builtin chips are resolved to Builtin");
+        };
+
+        let mut interface = Interface::default();
+        for PinDecl { name, width } in &chip.inputs {
+            interface
+                .inputs
+                .push((name.clone(), self.new_nets(*width, false)));
+        }
+        for PinDecl { name, width } in &chip.outputs {
+            interface
+                .outputs
+                .push((name.clone(), self.new_nets(*width, false)));
+        }
+
+        let mut instances: Vec<Interface> = Vec::new();
+        for (i, part) in parts.iter().enumerate() {
+            let parent = self.origin;
+            self.origins.push(Origin {
+                parent,
+                chip: chip.clone(),
+                part: i,
+            });
+            self.origin = Some(self.origins.len() - 1);
+            let result = self.instantiate(&part.name);
+            self.origin = parent;
+
+            let (instance, _) = result.map_err(|e| match e {
+                HdlError::UnknownChip(name) => chip
+                    .diagnostic(
+                        part.line,
+                        part.column,
+                        &part.name,
+                        format!("Chip '{}' was not found", name),
+                    )
+                    .into(),
+                e => e,
+            })?;
+            instances.push(instance);
+        }
+
+        let mut internal: HashMap<String, Vec<Net>> = HashMap::new();
+
+        // Outputs first, so internal pins have a width before they are read
+        for direction in [Direction::Out, Direction::In] {
+            for (part, instance) in parts.iter().zip(&instances) {
+                let mut connected: Vec<Vec<bool>> = instance
+                    .inputs
+                    .iter()
+                    .map(|(_, nets)| vec![false; nets.len()])
+                    .collect();
+
+                for connection in &part.connections {
+                    let pin = &connection.part_pin;
+                    let Some((pin_direction, pin_nets)) = instance.pin(&pin.name) else {
+                        return Err(chip
+                            .diagnostic(
+                                connection.line,
+                                connection.column,
+                                &pin.name,
+                                format!("Chip '{}' has no pin '{}'", part.name, pin.name),
+                            )
+                            .into());
+                    };
+                    if pin_direction != direction {
+                        continue;
+                    }
+
+                    let part_nets = self.slice(chip, connection, connection.column, pin, pin_nets)?;
+                    let (lo, _) = pin.range.unwrap_or((0, 0));
+                    if let Some(i) = instance
+                        .inputs
+                        .iter()
+                        .position(|(name, _)| *name == pin.name)
+                    {
+                        connected[i][lo..lo + part_nets.len()].fill(true);
+                    }
+
+                    let external_nets = self.external_nets(
+                        chip,
+                        connection,
+                        direction,
+                        &interface,
+                        &mut internal,
+                        part_nets.len(),
+                    )?;
+                    self.connect(chip, connection, &part_nets, &external_nets)?;
+                }
+
+                // Unconnected part inputs read as false
+                if direction == Direction::In {
+                    for ((_, nets), connected) in instance.inputs.iter().zip(&connected) {
+                        for (net, connected) in nets.iter().zip(connected) {
+                            if !connected {
+                                self.alias(*net, FALSE_NET).ok();
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        Ok((interface, internal))
+    }
+
+    /// The chip-side nets of a connection: a constant, a (sliced) chip pin
+    /// or an internal pin.
+    fn external_nets(
+        &mut self,
+        chip: &Chip,
+        connection: &Connection,
+        direction: Direction,
+        interface: &Interface,
+        internal: &mut HashMap<String, Vec<Net>>,
+        width: usize,
+    ) -> Result<Vec<Net>, HdlError> {
+        let error = |text: &str, message: String| -> HdlError {
+            chip
+                .diagnostic(connection.line, connection.external_column, text, message)
+                .into()
+        };
+
+        let external = match &connection.external {
+            Source::Constant(value) => {
+                if direction == Direction::Out {
+                    return Err(error(
+                        &value.to_string(),
+                        "A part's output can't be connected to a constant".to_string(),
+                    ));
+                }
+                let net = if *value { TRUE_NET } else { FALSE_NET };
+                return Ok(vec![net; width]);
+            }
+            Source::Pin(external) => external,
+        };
+
+        match (interface.pin(&external.name), direction) {
+            (Some((Direction::In, nets)), Direction::In)
+            | (Some((Direction::Out, nets)), Direction::Out) => {
+                self.slice(chip, connection, connection.external_column, external, nets)
+            }
+            (Some((Direction::In, _)), Direction::Out) => Err(error(
+                &external.name,
+                format!("Input pin '{}' can't be driven by a part", external.name),
+            )),
+            (Some((Direction::Out, _)), Direction::In) => Err(error(
+                &external.name,
+                format!(
+                    "Output pin '{}' can't be used as a part input, use an internal pin",
+                    external.name
+                ),
+            )),
+            (None, _) if external.range.is_some() => Err(error(
+                &external.name,
+                format!("Internal pin '{}' can't be sub-bused", external.name),
+            )),
+            (None, Direction::Out) => {
+                if let Some(nets) = internal.get(&external.name) {
+                    return Ok(nets.clone());
+                }
+                let nets = self.new_nets(width, false);
+                internal.insert(external.name.clone(), nets.clone());
+                Ok(nets)
+            }
+            (None, Direction::In) => internal.get(&external.name).cloned().ok_or_else(|| {
+                error(
+                    &external.name,
+                    format!("Internal pin '{}' is never driven", external.name),
+                )
+            }),
+        }
+    }
+
+    /// The part instances from the top-level chip down to `origin`.
+    fn origin_chain(&self, mut origin: Option<usize>) -> Vec<usize> {
+        let mut chain = Vec::new();
+        while let Some(node) = origin {
+            chain.push(node);
+            origin = self.origins[node].parent;
+        }
+        chain.reverse();
+
+        chain
+    }
+
+    /// Points at the part whose connections close `cycle`: the first level
+    /// of the hierarchy where the loop's components sit in different parts.
+    fn loop_error(&self, cycle: &[usize]) -> HdlError {
+        let chains: Vec<Vec<usize>> = cycle
+            .iter()
+            .map(|&component| self.origin_chain(self.component_origins[component]))
+            .collect();
+        let first = &chains[0];
+        let common = (0..first.len())
+            .take_while(|&level| chains.iter().all(|chain| chain.get(level) == first.get(level)))
+            .count();
+
+        let origin = &self.origins[first[common.min(first.len() - 1)]];
+        let Body::Parts(parts) = &origin.chip.body else {
+            unreachable!("only chips with parts have part instances");
+        };
+        let part = &parts[origin.part];
+
+        origin
+            .chip
+            .diagnostic(
+                part.line,
+                part.column,
+                &part.name,
+                format!("Part '{}' is in a combinational loop", part.name),
+            )
+            .into()
+    }
+}
+
+/// Gate-level simulation of a chip: combinational logic settles on
+/// [`ChipSimulator::eval`], clocked parts sample on `tick` and update on `tock`.
+pub struct ChipSimulator {
+    name: String,
+    components: Vec<Component>,
+    order: Vec<usize>,
+    values: Vec<bool>,
+    inputs: Vec<(String, Vec<Net>)>,
+    outputs: Vec<(String, Vec<Net>)>,
+    internal: HashMap<String, Vec<Net>>,
+    keyboard: u16,
+}
+
+fn read_word(values: &[bool], nets: &[Net]) -> u16 {
+    nets.iter().enumerate().fold(0, |word, (bit, net)| {
+        word | (u16::from(values[*net]) << bit)
+    })
+}
+
+impl ChipSimulator {
+    pub fn new(library: &mut ChipLibrary, name: &str) -> Result<Self, HdlError> {
+        let mut builder = Builder {
+            library,
+            parent: Vec::new(),
+            driven: Vec::new(),
+            components: Vec::new(),
+            stack: Vec::new(),
+            origins: Vec::new(),
+            origin: None,
+            component_origins: Vec::new(),
+        };
+        builder.new_net(true);
+        builder.new_net(true);
+
+        let (interface, internal) = builder.instantiate(name)?;
+        if interface
+            .inputs
+            .iter()
+            .chain(&interface.outputs)
+            .any(|(_, nets)| nets.len() > MAX_PIN_WIDTH)
+        {
+            return Err(HdlError::UnknownPin(format!(
+                "{} has pins wider than {} bits",
+                name, MAX_PIN_WIDTH
+            )));
+        }
+
+        // Renumber merged nets densely
+        let mut index: HashMap<Net, Net> = HashMap::new();
+        let mut compact = |builder: &mut Builder, net: Net| -> Net {
+            let root = builder.find(net);
+            let next = index.len();
+            *index.entry(root).or_insert(next)
+        };
+        compact(&mut builder, FALSE_NET);
+        compact(&mut builder, TRUE_NET);
+
+        let mut components = std::mem::take(&mut builder.components);
+        for component in components.iter_mut() {
+            for net in component.nets_mut() {
+                *net = compact(&mut builder, *net);
+            }
+        }
+        let mut remap = |pins: Vec<(String, Vec<Net>)>| -> Vec<(String, Vec<Net>)> {
+            pins.into_iter()
+                .map(|(pin, nets)| {
+                    (
+                        pin,
+                        nets.iter().map(|net| compact(&mut builder, *net)).collect(),
+                    )
+                })
+                .collect()
+        };
+        let inputs = remap(interface.inputs);
+        let outputs = remap(interface.outputs);
+        let internal = remap(internal.into_iter().collect()).into_iter().collect();
+
+        let order = Self::evaluation_order(&components, index.len())
+            .map_err(|cycle| builder.loop_error(&cycle))?;
+
+        let mut values = vec![false; index.len()];
+        values[TRUE_NET] = true;
+
+        let mut simulator = Self {
+            name: name.to_string(),
+            components,
+            order,
+            values,
+            inputs,
+            outputs,
+            internal,
+            keyboard: 0,
+        };
+        simulator.eval();
+
+        Ok(simulator)
+    }
+
+    /// Topological order of the components along combinational paths, or
+    /// the components of a loop that prevents one.
+    fn evaluation_order(
+        components: &[Component],
+        net_count: usize,
+    ) -> Result<Vec<usize>, Vec<usize>> {
+        let mut driver: Vec<Option<usize>> = vec![None; net_count];
+        for (i, component) in components.iter().enumerate() {
+            for net in component.outputs() {
+                driver[net] = Some(i);
+            }
+        }
+
+        let mut dependents: Vec<Vec<usize>> = vec![Vec::new(); components.len()];
+        let mut pending: Vec<usize> = vec![0; components.len()];
+        for (i, component) in components.iter().enumerate() {
+            for net in component.combinational_inputs() {
+                if let Some(source) = driver[net] {
+                    dependents[source].push(i);
+                    pending[i] += 1;
+                }
+            }
+        }
+
+        let mut ready: VecDeque<usize> =
+            (0..components.len()).filter(|i| pending[*i] == 0).collect();
+        let mut order: Vec<usize> = Vec::with_capacity(components.len());
+        while let Some(i) = ready.pop_front() {
+            order.push(i);
+            for dependent in &dependents[i] {
+                pending[*dependent] -= 1;
+                if pending[*dependent] == 0 {
+                    ready.push_back(*dependent);
+                }
+            }
+        }
+
+        if order.len() == components.len() {
+            return Ok(order);
+        }
+
+        // Components left over wait on a driver that is also left over, so
+        // walking back through those drivers has to come round to a repeat
+        let blocked_driver = |i: usize| {
+            components[i]
+                .combinational_inputs()
+                .into_iter()
+                .filter_map(|net| driver[net])
+                .find(|source| pending[*source] > 0)
+                .expect("a blocked component has a blocked driver")
+        };
+        let mut current = (0..components.len())
+            .find(|i| pending[*i] > 0)
+            .expect("some component is blocked");
+        let mut seen = vec![false; components.len()];
+        while !seen[current] {
+            seen[current] = true;
+            current = blocked_driver(current);
+        }
+
+        let mut cycle = vec![current];
+        let mut next = blocked_driver(current);
+        while next != current {
+            cycle.push(next);
+            next = blocked_driver(next);
+        }
+
+        Err(cycle)
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn input_pins(&self) -> Vec<(&str, usize)> {
+        self.inputs
+            .iter()
+            .map(|(pin, nets)| (pin.as_str(), nets.len()))
+            .collect()
+    }
+
+    pub fn output_pins(&self) -> Vec<(&str, usize)> {
+        self.outputs
+            .iter()
+            .map(|(pin, nets)| (pin.as_str(), nets.len()))
+            .collect()
+    }
+
+    fn pin_nets(&self, pin: &str) -> Option<&Vec<Net>> {
+        self.inputs
+            .iter()
+            .chain(&self.outputs)
+            .find(|(name, _)| name == pin)
+            .map(|(_, nets)| nets)
+            .or_else(|| self.internal.get(pin))
+    }
+
+    /// Sets an input pin; call [`ChipSimulator::eval`] to propagate it.
+    pub fn set(&mut self, pin: &str, value: u16) -> Result<(), HdlError> {
+        let nets = self
+            .inputs
+            .iter()
+            .find(|(name, _)| name == pin)
+            .map(|(_, nets)| nets.clone())
+            .ok_or_else(|| HdlError::UnknownPin(pin.to_string()))?;
+
+        if nets.len() < 16 && value >> nets.len() != 0 {
+            return Err(HdlError::ValueTooWide(pin.to_string(), value.to_string()));
+        }
+
+        for (bit, net) in nets.iter().enumerate() {
+            self.values[*net] = value & (1 << bit) != 0;
+        }
+
+        Ok(())
+    }
+
+    /// Reads an input, output or internal pin of the top-level chip.
+    pub fn get(&self, pin: &str) -> Result<u16, HdlError> {
+        let nets = self
+            .pin_nets(pin)
+            .ok_or_else(|| HdlError::UnknownPin(pin.to_string()))?;
+
+        Ok(read_word(&self.values, nets))
+    }
+
+    pub fn set_keyboard(&mut self, key: u16) {
+        self.keyboard = key;
+    }
+
+    /// Loads a program into every ROM32K in the design.
+    pub fn load_rom(&mut self, program: &[u16]) {
+        for component in self.components.iter_mut() {
+            if let Component::Memory {
+                data, rom: true, ..
+            } = component
+            {
+                data.fill(0);
+                let len = program.len().min(data.len());
+                data[..len].copy_from_slice(&program[..len]);
+            }
+        }
+    }
+
+    fn write_word(values: &mut [bool], nets: &[Net], word: u16) {
+        for (bit, net) in nets.iter().enumerate() {
+            values[*net] = word & (1 << bit) != 0;
+        }
+    }
+
+    /// Settles the combinational logic for the current inputs and state.
+    pub fn eval(&mut self) {
+        for &i in &self.order {
+            match &self.components[i] {
+                Component::Nand { a, b, out } => {
+                    self.values[*out] = !(self.values[*a] && self.values[*b]);
+                }
+                Component::Dff { output, state, .. } => self.values[*output] = *state,
+                Component::Register { output, state, .. } => {
+                    Self::write_word(&mut self.values, output, *state);
+                }
+                Component::Memory {
+                    address,
+                    output,
+                    data,
+                    ..
+                } => {
+                    let word = data[read_word(&self.values, address) as usize];
+                    Self::write_word(&mut self.values, output, word);
+                }
+                Component::Keyboard { output } => {
+                    Self::write_word(&mut self.values, output, self.keyboard);
+                }
+            }
+        }
+    }
+
+    /// Rising clock edge: clocked parts sample their inputs.
+    pub fn tick(&mut self) {
+        self.eval();
+
+        let values = &self.values;
+        for component in self.components.iter_mut() {
+            match component {
+                Component::Dff { input, next, .. } => *next = values[*input],
+                Component::Register {
+                    input,
+                    load,
+                    state,
+                    next,
+                    ..
+                } => {
+                    *next = if values[*load] {
+                        read_word(values, input)
+                    } else {
+                        *state
+                    };
+                }
+                Component::Memory {
+                    input,
+                    load,
+                    address,
+                    pending,
+                    rom: false,
+                    ..
+                } => {
+                    *pending = values[*load].then(|| {
+                        (
+                            read_word(values, address) as usize,
+                            read_word(values, input),
+                        )
+                    });
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Falling clock edge: clocked parts commit what they sampled.
+    pub fn tock(&mut self) {
+        for component in self.components.iter_mut() {
+            match component {
+                Component::Dff { state, next, .. } => *state = *next,
+                Component::Register { state, next, .. } => *state = *next,
+                Component::Memory { data, pending, .. } => {
+                    if let Some((address, word)) = pending.take() {
+                        data[address] = word;
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        self.eval();
+    }
+}
@@ -0,0 +1,194 @@
+use hack_hdl::{ChipLibrary, ChipSimulator, HdlError};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// The project directories, so chips use each other's `.hdl` files rather
+/// than the built-in versions.
+fn project_dirs() -> Vec<PathBuf> {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("..");
+    ["01", "02", "03", "05"]
+        .iter()
+        .map(|project| root.join(project))
+        .collect()
+}
+
+fn load(name: &str) -> ChipSimulator {
+    let mut library = ChipLibrary::new(project_dirs());
+    ChipSimulator::new(&mut library, name).unwrap_or_else(|e| panic!("{}: {}", name, e))
+}
+
+fn set(simulator: &mut ChipSimulator, pins: &[(&str, u16)]) {
+    for (pin, value) in pins {
+        simulator.set(pin, *value).expect("input pin");
+    }
+    simulator.eval();
+}
+
+fn get(simulator: &ChipSimulator, pin: &str) -> u16 {
+    simulator.get(pin).expect("pin")
+}
+
+fn clock(simulator: &mut ChipSimulator) {
+    simulator.tick();
+    simulator.tock();
+}
+
+/// Loads `source` as the only chip in a scratch directory, on top of the
+/// project chips.
+fn load_source(name: &str, source: &str) -> Result<ChipSimulator, HdlError> {
+    let dir = std::env::temp_dir().join(format!("hdl-{}-{}", std::process::id(), name));
+    fs::create_dir_all(&dir).expect("scratch directory");
+    fs::write(dir.join(format!("{}.hdl", name)), source).expect("write .hdl file");
+
+    let mut dirs = vec![dir.clone()];
+    dirs.extend(project_dirs());
+    let result = ChipSimulator::new(&mut ChipLibrary::new(dirs), name);
+    fs::remove_dir_all(&dir).ok();
+
+    result
+}
+
+/// The (line, column, message) of a diagnostic error.
+fn position(error: HdlError) -> (usize, usize, String) {
+    match error {
+        HdlError::Diagnostic(diagnostic) => {
+            (diagnostic.line, diagnostic.column, diagnostic.message)
+        }
+        e => panic!("expected a diagnostic, got '{}'", e),
+    }
+}
+
+#[test]
+fn alu_adds() {
+    let mut alu = load("ALU");
+    set(&mut alu, &[("x", 5), ("y", 3), ("f", 1)]);
+
+    assert_eq!(get(&alu, "out"), 8);
+    assert_eq!(get(&alu, "zr"), 0);
+    assert_eq!(get(&alu, "ng"), 0);
+}
+
+#[test]
+fn alu_computes_x_minus_y() {
+    // zx nx zy ny f no = 0 1 0 0 1 1
+    let mut alu = load("ALU");
+    set(
+        &mut alu,
+        &[("x", 3), ("y", 5), ("nx", 1), ("f", 1), ("no", 1)],
+    );
+
+    assert_eq!(get(&alu, "out") as i16, -2);
+    assert_eq!(get(&alu, "ng"), 1);
+}
+
+#[test]
+fn mux8way16_selects_its_seventh_input() {
+    let mut mux = load("Mux8Way16");
+    let inputs = ["a", "b", "c", "d", "e", "f", "g", "h"];
+    for (i, pin) in inputs.iter().enumerate() {
+        mux.set(pin, 100 + i as u16).expect("input pin");
+    }
+    set(&mut mux, &[("sel", 6)]);
+
+    assert_eq!(get(&mux, "out"), 106);
+}
+
+#[test]
+fn pc_loads_then_increments() {
+    let mut pc = load("PC");
+    set(&mut pc, &[("in", 1234), ("load", 1)]);
+    assert_eq!(get(&pc, "out"), 0, "out only changes on the clock");
+
+    clock(&mut pc);
+    assert_eq!(get(&pc, "out"), 1234);
+
+    set(&mut pc, &[("load", 0), ("inc", 1)]);
+    clock(&mut pc);
+    assert_eq!(get(&pc, "out"), 1235);
+
+    set(&mut pc, &[("reset", 1)]);
+    clock(&mut pc);
+    assert_eq!(get(&pc, "out"), 0);
+}
+
+#[test]
+fn ram8_reads_back_a_write() {
+    let mut ram = load("RAM8");
+    set(&mut ram, &[("address", 5), ("in", 42), ("load", 1)]);
+    clock(&mut ram);
+
+    set(&mut ram, &[("load", 0), ("in", 7)]);
+    clock(&mut ram);
+    assert_eq!(get(&ram, "out"), 42);
+
+    set(&mut ram, &[("address", 3)]);
+    assert_eq!(get(&ram, "out"), 0);
+}
+
+#[test]
+fn cpu_runs_a_load_and_a_store() {
+    let mut cpu = load("CPU");
+
+    // @5
+    set(&mut cpu, &[("instruction", 5)]);
+    clock(&mut cpu);
+    assert_eq!(get(&cpu, "addressM"), 5);
+    assert_eq!(get(&cpu, "pc"), 1);
+
+    // D=A
+    set(&mut cpu, &[("instruction", 0b1110_1100_0001_0000)]);
+    clock(&mut cpu);
+
+    // M=D
+    set(&mut cpu, &[("instruction", 0b1110_0011_0000_1000)]);
+    assert_eq!(get(&cpu, "outM"), 5);
+    assert_eq!(get(&cpu, "writeM"), 1);
+    assert_eq!(get(&cpu, "addressM"), 5);
+}
+
+/// `in=in`: the caret belongs under the chip's `in`, not the part's.
+#[test]
+fn width_mismatch_points_at_the_chip_side_pin() {
+    let source = "\
+CHIP Wide {
+    IN in;
+    OUT out[16];
+
+    PARTS:
+    Not16(in=in, out=out);
+}
+";
+    let error = load_source("Wide", source).err().expect("width mismatch");
+
+    assert_eq!(
+        position(error),
+        (
+            6,
+            14,
+            "Width mismatch: 16 bit(s) connected to 1 bit(s)".to_string()
+        )
+    );
+}
+
+#[test]
+fn combinational_loop_points_at_a_part_in_it() {
+    let source = "\
+CHIP Loop {
+    IN a;
+    OUT out;
+
+    PARTS:
+    And(a=a, b=a, out=out);
+    Not(in=x, out=y);
+    Not(in=y, out=x);
+}
+";
+    let error = load_source("Loop", source)
+        .err()
+        .expect("combinational loop");
+
+    assert_eq!(
+        position(error),
+        (7, 5, "Part 'Not' is in a combinational loop".to_string())
+    );
+}
@@ -0,0 +1,44 @@
+use std::fmt;
+
+#[derive(Debug)]
+pub enum ScriptError {
+    Io(String, std::io::Error),
+    Syntax(usize, String),
+    UnknownCommand(usize, String),
+    UnknownVariable(String),
+    InvalidValue(String),
+    InvalidFormat(String),
+    Load(String, String),
+    Runtime(String),
+    ComparisonFailure {
+        line: usize,
+        expected: String,
+        actual: String,
+    },
+}
+
+impl fmt::Display for ScriptError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScriptError::Io(path, e) => write!(f, "{}: {}", path, e),
+            ScriptError::Syntax(line, message) => write!(f, "Line {}: {}", line, message),
+            ScriptError::UnknownCommand(line, command) => {
+                write!(f, "Line {}: Unknown command '{}'", line, command)
+            }
+            ScriptError::UnknownVariable(variable) => write!(f, "Unknown variable '{}'", variable),
+            ScriptError::InvalidValue(value) => write!(f, "Invalid value '{}'", value),
+            ScriptError::InvalidFormat(format) => write!(f, "Invalid output format '{}'", format),
+            ScriptError::Load(path, message) => write!(f, "Unable to load {}: {}", path, message),
+            ScriptError::Runtime(message) => write!(f, "Runtime error: {}", message),
+            ScriptError::ComparisonFailure {
+                line,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "Comparison failure at line {}\n  expected: {}\n  actual:   {}",
+                line, expected, actual
+            ),
+        }
+    }
+}
@@ -0,0 +1,9 @@
+pub mod error;
+pub mod output;
+pub mod runner;
+pub mod script;
+pub mod simulator;
+
+pub use error::ScriptError;
+pub use runner::{Runner, run_script};
+pub use simulator::Simulator;
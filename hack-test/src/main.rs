@@ -0,0 +1,31 @@
+use std::env;
+use std::path::Path;
+use std::process;
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    if args.len() < 2 {
+        println!("Usage: cargo run -- <script.tst> [script.tst ...]");
+        return;
+    }
+
+    let mut failed = false;
+    for script in &args[1..] {
+        match hack_test::run_script(Path::new(script)) {
+            Ok(runner) => {
+                for message in runner.echo() {
+                    println!("{}", message);
+                }
+                println!("{}: End of script - Comparison ended successfully", script);
+            }
+            Err(e) => {
+                eprintln!("{}: {}", script, e);
+                failed = true;
+            }
+        }
+    }
+
+    if failed {
+        process::exit(1);
+    }
+}
@@ -0,0 +1,120 @@
+use crate::error::ScriptError;
+
+/// A value read from a simulator, e.g. `RAM[0]` or `time`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Number(i16),
+    Text(String),
+}
+
+/// One `output-list` entry such as `RAM[256]%D1.6.1`: the variable, the
+/// format letter (D, X, B or S) and the left padding, field length and
+/// right padding of its column.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OutputColumn {
+    pub variable: String,
+    pub format: char,
+    pub pad_left: usize,
+    pub len: usize,
+    pub pad_right: usize,
+}
+
+impl OutputColumn {
+    pub fn parse(item: &str) -> Result<Self, ScriptError> {
+        let invalid = || ScriptError::InvalidFormat(item.to_string());
+
+        let Some((variable, spec)) = item.split_once('%') else {
+            return Ok(Self {
+                variable: item.to_string(),
+                format: 'D',
+                pad_left: 1,
+                len: 6,
+                pad_right: 1,
+            });
+        };
+
+        let mut chars = spec.chars();
+        let format = chars.next().ok_or_else(invalid)?;
+        if !"DXBS".contains(format) {
+            return Err(invalid());
+        }
+
+        let widths: Vec<usize> = chars
+            .as_str()
+            .split('.')
+            .map(|width| width.parse::<usize>().map_err(|_| invalid()))
+            .collect::<Result<_, _>>()?;
+        let [pad_left, len, pad_right] = widths[..] else {
+            return Err(invalid());
+        };
+
+        Ok(Self {
+            variable: variable.to_string(),
+            format,
+            pad_left,
+            len,
+            pad_right,
+        })
+    }
+
+    fn width(&self) -> usize {
+        self.pad_left + self.len + self.pad_right
+    }
+
+    /// The column title: the variable name centred in the column width.
+    pub fn header(&self) -> String {
+        let width = self.width();
+        let name: String = self.variable.chars().take(width).collect();
+        let left = (width - name.len()) / 2;
+
+        format!("{}{}{}", " ".repeat(left), name, " ".repeat(width - name.len() - left))
+    }
+
+    pub fn format_value(&self, value: &Value) -> String {
+        let field = match (self.format, value) {
+            ('D', Value::Number(n)) => format!("{:>1$}", n, self.len),
+            ('X', Value::Number(n)) => {
+                let hex = format!("{:0>1$X}", *n as u16, self.len);
+                hex[hex.len().saturating_sub(self.len)..].to_string()
+            }
+            ('B', Value::Number(n)) => {
+                let bin = format!("{:0>1$b}", *n as u16, self.len);
+                bin[bin.len().saturating_sub(self.len)..].to_string()
+            }
+            ('S', Value::Number(n)) => format!("{:<1$}", n, self.len),
+            ('S', Value::Text(text)) => format!("{:<1$}", text, self.len),
+            (_, Value::Text(text)) => format!("{:>1$}", text, self.len),
+            _ => unreachable!(),
+        };
+
+        format!(
+            "{}{}{}",
+            " ".repeat(self.pad_left),
+            field,
+            " ".repeat(self.pad_right)
+        )
+    }
+}
+
+/// Parses a `set` value: decimal by default, or `%D`, `%X` and `%B` prefixed.
+pub fn parse_value(value: &str) -> Result<i16, ScriptError> {
+    let invalid = || ScriptError::InvalidValue(value.to_string());
+
+    let (radix, digits) = match value.strip_prefix('%') {
+        Some(rest) => match rest.split_at_checked(1) {
+            Some(("D", digits)) => (10, digits),
+            Some(("X", digits)) => (16, digits),
+            Some(("B", digits)) => (2, digits),
+            _ => return Err(invalid()),
+        },
+        None => (10, value),
+    };
+
+    // Accept both signed values and their unsigned 16-bit spelling
+    let number = i32::from_str_radix(digits, radix).map_err(|_| invalid())?;
+    if !(i16::MIN as i32..=u16::MAX as i32).contains(&number) {
+        return Err(invalid());
+    }
+
+    Ok(number as u16 as i16)
+}
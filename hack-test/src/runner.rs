@@ -0,0 +1,226 @@
+use crate::error::ScriptError;
+use crate::output::{OutputColumn, Value, parse_value};
+use crate::script::{Command, Condition, Statement, parse_script};
+use crate::simulator::{CpuSimulator, Simulator, VmSimulator};
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Executes a parsed test script against a simulator, writing the `.out`
+/// file and checking each output line against the `.cmp` file as it goes.
+pub struct Runner {
+    script_dir: PathBuf,
+    simulator: Option<Box<dyn Simulator>>,
+    output_columns: Vec<OutputColumn>,
+    output_file: Option<File>,
+    compare_lines: Option<Vec<String>>,
+    output_line_count: usize,
+    time: u64,
+    half_cycle: bool,
+    echo: Vec<String>,
+}
+
+impl Runner {
+    pub fn new(script_dir: &Path) -> Self {
+        Self {
+            script_dir: script_dir.to_path_buf(),
+            simulator: None,
+            output_columns: Vec::new(),
+            output_file: None,
+            compare_lines: None,
+            output_line_count: 0,
+            time: 0,
+            half_cycle: false,
+            echo: Vec::new(),
+        }
+    }
+
+    /// Messages from `echo` commands, in order.
+    pub fn echo(&self) -> &[String] {
+        &self.echo
+    }
+
+    /// Picks the simulator for a `load` target from its extension;
+    /// directories hold `.vm` files.
+    fn simulator_for(&self, path: &Path) -> Result<Box<dyn Simulator>, ScriptError> {
+        if path.is_dir() {
+            return Ok(Box::new(VmSimulator::default()));
+        }
+
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("asm") | Some("hack") => Ok(Box::new(CpuSimulator::default())),
+            Some("vm") => Ok(Box::new(VmSimulator::default())),
+            _ => Err(ScriptError::Load(
+                path.display().to_string(),
+                "no simulator can run this file".to_string(),
+            )),
+        }
+    }
+
+    fn simulator(&mut self) -> Result<&mut Box<dyn Simulator>, ScriptError> {
+        self.simulator
+            .as_mut()
+            .ok_or_else(|| ScriptError::Load(String::new(), "no program loaded".to_string()))
+    }
+
+    fn get(&mut self, variable: &str) -> Result<Value, ScriptError> {
+        if variable == "time" {
+            let suffix = if self.half_cycle { "+" } else { "" };
+            return Ok(Value::Text(format!("{}{}", self.time, suffix)));
+        }
+
+        self.simulator()?.get(variable)
+    }
+
+    fn write_output_line(&mut self, line: String) -> Result<(), ScriptError> {
+        if let Some(output_file) = self.output_file.as_mut() {
+            writeln!(output_file, "{}", line)
+                .map_err(|e| ScriptError::Io("output file".to_string(), e))?;
+        }
+
+        self.output_line_count += 1;
+
+        if let Some(compare_lines) = &self.compare_lines {
+            let expected = compare_lines
+                .get(self.output_line_count - 1)
+                .map(String::as_str)
+                .unwrap_or("");
+
+            if !lines_match(expected, &line) {
+                return Err(ScriptError::ComparisonFailure {
+                    line: self.output_line_count,
+                    expected: expected.to_string(),
+                    actual: line,
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    fn condition_holds(&mut self, condition: &Condition) -> Result<bool, ScriptError> {
+        let Value::Number(actual) = self.get(&condition.variable)? else {
+            return Err(ScriptError::UnknownVariable(condition.variable.clone()));
+        };
+        let expected = parse_value(&condition.value)?;
+
+        Ok(match condition.operator.as_str() {
+            "=" => actual == expected,
+            "<>" => actual != expected,
+            "<" => actual < expected,
+            ">" => actual > expected,
+            "<=" => actual <= expected,
+            ">=" => actual >= expected,
+            operator => return Err(ScriptError::InvalidValue(operator.to_string())),
+        })
+    }
+
+    pub fn run(&mut self, statements: &[Statement]) -> Result<(), ScriptError> {
+        for statement in statements {
+            self.execute(statement)?;
+        }
+
+        Ok(())
+    }
+
+    fn execute(&mut self, statement: &Statement) -> Result<(), ScriptError> {
+        match &statement.command {
+            Command::Load(file) => {
+                // A bare `load` loads the script's own directory, as the
+                // VM emulator scripts for multi-file programs do
+                let path = match file {
+                    Some(file) => self.script_dir.join(file),
+                    None => self.script_dir.clone(),
+                };
+                let mut simulator = self.simulator_for(&path)?;
+                simulator.load(&path)?;
+                self.simulator = Some(simulator);
+            }
+            Command::OutputFile(file) => {
+                let path = self.script_dir.join(file);
+                let output_file = File::create(&path)
+                    .map_err(|e| ScriptError::Io(path.display().to_string(), e))?;
+                self.output_file = Some(output_file);
+            }
+            Command::CompareTo(file) => {
+                let path = self.script_dir.join(file);
+                let contents = fs::read_to_string(&path)
+                    .map_err(|e| ScriptError::Io(path.display().to_string(), e))?;
+                self.compare_lines = Some(contents.lines().map(str::to_string).collect());
+            }
+            Command::OutputList(items) => {
+                self.output_columns = items
+                    .iter()
+                    .map(|item| OutputColumn::parse(item))
+                    .collect::<Result<_, _>>()?;
+
+                let header: Vec<String> =
+                    self.output_columns.iter().map(OutputColumn::header).collect();
+                self.write_output_line(format!("|{}|", header.join("|")))?;
+            }
+            Command::Set(variable, value) => {
+                let value = parse_value(value)?;
+                self.simulator()?.set(variable, value)?;
+            }
+            Command::Output => {
+                let mut cells: Vec<String> = Vec::new();
+                for column in self.output_columns.clone() {
+                    let value = self.get(&column.variable)?;
+                    cells.push(column.format_value(&value));
+                }
+                self.write_output_line(format!("|{}|", cells.join("|")))?;
+            }
+            Command::Echo(text) => self.echo.push(text.clone()),
+            Command::ClearEcho => self.echo.clear(),
+            // Breakpoints only pause the interactive simulators
+            Command::Breakpoint(_, _) | Command::ClearBreakpoints => {}
+            Command::Repeat(count, body) => {
+                for _ in 0..*count {
+                    self.run(body)?;
+                }
+            }
+            Command::While(condition, body) => {
+                while self.condition_holds(condition)? {
+                    self.run(body)?;
+                }
+            }
+            Command::Simulator(command) => {
+                if !self.simulator()?.execute(command)? {
+                    return Err(ScriptError::UnknownCommand(statement.line, command.clone()));
+                }
+
+                match command.as_str() {
+                    "tick" => self.half_cycle = true,
+                    "tock" | "ticktock" => {
+                        self.half_cycle = false;
+                        self.time += 1;
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// `*` in a comparison file matches any character in that position.
+fn lines_match(expected: &str, actual: &str) -> bool {
+    expected.chars().count() == actual.chars().count()
+        && expected
+            .chars()
+            .zip(actual.chars())
+            .all(|(e, a)| e == '*' || e == a)
+}
+
+/// Parses and runs the `.tst` script at `path`; file names inside the
+/// script are resolved relative to the script's directory.
+pub fn run_script(path: &Path) -> Result<Runner, ScriptError> {
+    let source = fs::read_to_string(path).map_err(|e| ScriptError::Io(path.display().to_string(), e))?;
+    let statements = parse_script(&source)?;
+
+    let mut runner = Runner::new(path.parent().unwrap_or(Path::new(".")));
+    runner.run(&statements)?;
+
+    Ok(runner)
+}
@@ -0,0 +1,261 @@
+use crate::error::ScriptError;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Command {
+    Load(Option<String>),
+    OutputFile(String),
+    CompareTo(String),
+    OutputList(Vec<String>),
+    Set(String, String),
+    Output,
+    Echo(String),
+    ClearEcho,
+    Breakpoint(String, String),
+    ClearBreakpoints,
+    Repeat(usize, Vec<Statement>),
+    While(Condition, Vec<Statement>),
+    /// Simulator-specific commands such as `ticktock` or `vmstep`
+    Simulator(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Condition {
+    pub variable: String,
+    pub operator: String,
+    pub value: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Statement {
+    pub line: usize,
+    pub command: Command,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Word(String),
+    Text(String),
+    /// `,`, `;` or `!`. The last two end an output step or the whole script
+    /// in the interactive simulators, but all three behave alike headless.
+    Terminator,
+    OpenBrace,
+    CloseBrace,
+}
+
+fn ends_word(chars: &[char], i: usize) -> bool {
+    let starts_comment = chars[i] == '/' && matches!(chars.get(i + 1), Some('/') | Some('*'));
+
+    chars[i].is_whitespace() || ",;!{}\"".contains(chars[i]) || starts_comment
+}
+
+fn tokenize(source: &str) -> Result<Vec<(usize, Token)>, ScriptError> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut tokens: Vec<(usize, Token)> = Vec::new();
+    let mut line: usize = 1;
+    let mut i: usize = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+
+        if c == '\n' {
+            line += 1;
+            i += 1;
+        } else if c.is_whitespace() {
+            i += 1;
+        } else if c == '/' && chars.get(i + 1) == Some(&'/') {
+            while i < chars.len() && chars[i] != '\n' {
+                i += 1;
+            }
+        } else if c == '/' && chars.get(i + 1) == Some(&'*') {
+            i += 2;
+            while i < chars.len() && !(chars[i] == '*' && chars.get(i + 1) == Some(&'/')) {
+                if chars[i] == '\n' {
+                    line += 1;
+                }
+                i += 1;
+            }
+            i += 2;
+        } else if c == '"' {
+            let start = i + 1;
+            i = start;
+            while i < chars.len() && chars[i] != '"' {
+                i += 1;
+            }
+            if i >= chars.len() {
+                return Err(ScriptError::Syntax(line, "Unterminated string".to_string()));
+            }
+            tokens.push((line, Token::Text(chars[start..i].iter().collect())));
+            i += 1;
+        } else if c == ',' || c == ';' || c == '!' {
+            tokens.push((line, Token::Terminator));
+            i += 1;
+        } else if c == '{' {
+            tokens.push((line, Token::OpenBrace));
+            i += 1;
+        } else if c == '}' {
+            tokens.push((line, Token::CloseBrace));
+            i += 1;
+        } else {
+            let start = i;
+            while i < chars.len() && !ends_word(&chars, i) {
+                i += 1;
+            }
+            tokens.push((line, Token::Word(chars[start..i].iter().collect())));
+        }
+    }
+
+    Ok(tokens)
+}
+
+struct ScriptParser {
+    tokens: Vec<(usize, Token)>,
+    position: usize,
+}
+
+impl ScriptParser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position).map(|(_, token)| token)
+    }
+
+    fn line(&self) -> usize {
+        self.tokens
+            .get(self.position)
+            .or(self.tokens.last())
+            .map(|(line, _)| *line)
+            .unwrap_or(0)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position).map(|(_, token)| token.clone());
+        self.position += 1;
+        token
+    }
+
+    fn word(&mut self, what: &str) -> Result<String, ScriptError> {
+        let line = self.line();
+        match self.next() {
+            Some(Token::Word(word)) => Ok(word),
+            _ => Err(ScriptError::Syntax(line, format!("Expected {}", what))),
+        }
+    }
+
+    fn words_until_terminator(&mut self) -> Vec<String> {
+        let mut words: Vec<String> = Vec::new();
+        while let Some(Token::Word(word)) = self.peek() {
+            words.push(word.clone());
+            self.position += 1;
+        }
+        words
+    }
+
+    fn terminator(&mut self) -> Result<(), ScriptError> {
+        let line = self.line();
+        match self.next() {
+            Some(Token::Terminator) => Ok(()),
+            _ => Err(ScriptError::Syntax(line, "Expected ',', ';' or '!'".to_string())),
+        }
+    }
+
+    fn block(&mut self) -> Result<Vec<Statement>, ScriptError> {
+        let line = self.line();
+        if self.next() != Some(Token::OpenBrace) {
+            return Err(ScriptError::Syntax(line, "Expected '{'".to_string()));
+        }
+
+        let mut statements: Vec<Statement> = Vec::new();
+        loop {
+            match self.peek() {
+                Some(Token::CloseBrace) => {
+                    self.position += 1;
+                    return Ok(statements);
+                }
+                None => return Err(ScriptError::Syntax(line, "Unterminated block".to_string())),
+                _ => statements.push(self.statement()?),
+            }
+        }
+    }
+
+    fn statement(&mut self) -> Result<Statement, ScriptError> {
+        let line = self.line();
+        let name = self.word("a command")?;
+
+        let command = match name.as_str() {
+            "load" => {
+                let file = self.words_until_terminator().into_iter().next();
+                Command::Load(file)
+            }
+            "output-file" => Command::OutputFile(self.word("a file name")?),
+            "compare-to" => Command::CompareTo(self.word("a file name")?),
+            "output-list" => Command::OutputList(self.words_until_terminator()),
+            "set" => {
+                let variable = self.word("a variable")?;
+                let value = self.word("a value")?;
+                Command::Set(variable, value)
+            }
+            "output" => Command::Output,
+            "echo" => match self.next() {
+                Some(Token::Text(text)) | Some(Token::Word(text)) => Command::Echo(text),
+                _ => return Err(ScriptError::Syntax(line, "Expected text to echo".to_string())),
+            },
+            "clear-echo" => Command::ClearEcho,
+            "breakpoint" => {
+                let variable = self.word("a variable")?;
+                let value = self.word("a value")?;
+                Command::Breakpoint(variable, value)
+            }
+            "clear-breakpoints" => Command::ClearBreakpoints,
+            "repeat" => {
+                let count = match self.peek() {
+                    Some(Token::Word(count)) => {
+                        let count = count
+                            .parse::<usize>()
+                            .map_err(|_| ScriptError::InvalidValue(count.clone()))?;
+                        self.position += 1;
+                        count
+                    }
+                    _ => {
+                        return Err(ScriptError::Syntax(
+                            line,
+                            "repeat without a count never ends when running headless".to_string(),
+                        ));
+                    }
+                };
+                return Ok(Statement {
+                    line,
+                    command: Command::Repeat(count, self.block()?),
+                });
+            }
+            "while" => {
+                let condition = Condition {
+                    variable: self.word("a variable")?,
+                    operator: self.word("a comparison operator")?,
+                    value: self.word("a value")?,
+                };
+                return Ok(Statement {
+                    line,
+                    command: Command::While(condition, self.block()?),
+                });
+            }
+            _ => Command::Simulator(name),
+        };
+
+        self.terminator()?;
+
+        Ok(Statement { line, command })
+    }
+}
+
+/// Parses a `.tst` script into its list of top-level statements.
+pub fn parse_script(source: &str) -> Result<Vec<Statement>, ScriptError> {
+    let mut parser = ScriptParser {
+        tokens: tokenize(source)?,
+        position: 0,
+    };
+
+    let mut statements: Vec<Statement> = Vec::new();
+    while parser.peek().is_some() {
+        statements.push(parser.statement()?);
+    }
+
+    Ok(statements)
+}
@@ -0,0 +1,204 @@
+use crate::error::ScriptError;
+use crate::output::Value;
+use cpu_emulator::{Computer, parse_hack};
+use std::fs;
+use std::path::Path;
+use vm_translator::emulator::{ARG, LCL, MEMORY_SIZE, SP, TEMP, THAT, THIS, VmEmulator};
+use vm_translator::parser::{Parser, collect_input_filepaths};
+
+/// An emulator a test script can drive.
+pub trait Simulator {
+    /// Loads the program named by a `load` command.
+    fn load(&mut self, path: &Path) -> Result<(), ScriptError>;
+
+    fn get(&self, variable: &str) -> Result<Value, ScriptError>;
+
+    fn set(&mut self, variable: &str, value: i16) -> Result<(), ScriptError>;
+
+    /// Runs a simulator-specific command such as `ticktock`. Returns
+    /// `Ok(false)` when the command isn't one this simulator knows.
+    fn execute(&mut self, command: &str) -> Result<bool, ScriptError>;
+}
+
+/// Splits `RAM[256]` into `("RAM", 256)`.
+pub fn indexed_variable(variable: &str) -> Option<(&str, usize)> {
+    let (name, rest) = variable.split_once('[')?;
+    let index = rest.strip_suffix(']')?.parse::<usize>().ok()?;
+
+    Some((name, index))
+}
+
+/// The CPU emulator: runs `.hack` programs, assembling `.asm` ones first.
+#[derive(Default)]
+pub struct CpuSimulator {
+    computer: Computer,
+}
+
+impl Simulator for CpuSimulator {
+    fn load(&mut self, path: &Path) -> Result<(), ScriptError> {
+        let display = path.display().to_string();
+        let source =
+            fs::read_to_string(path).map_err(|e| ScriptError::Io(display.clone(), e))?;
+
+        let program: Vec<u16> = if path.extension().is_some_and(|ext| ext == "asm") {
+            let options = hack_asm::Options {
+                file_name: display.clone(),
+                ..hack_asm::Options::default()
+            };
+            hack_asm::assemble_with_options(&source, &options)
+                .map_err(|diagnostics| ScriptError::Load(display.clone(), diagnostics.to_string()))?
+                .words
+        } else {
+            parse_hack(&source).map_err(|e| ScriptError::Load(display.clone(), e.to_string()))?
+        };
+
+        self.computer = Computer::new(&program)
+            .map_err(|e| ScriptError::Load(display, e.to_string()))?;
+
+        Ok(())
+    }
+
+    fn get(&self, variable: &str) -> Result<Value, ScriptError> {
+        let value: u16 = match variable {
+            "A" => self.computer.a(),
+            "D" => self.computer.d(),
+            "PC" => self.computer.pc(),
+            _ => match indexed_variable(variable) {
+                Some(("RAM", index)) if index <= cpu_emulator::computer::KBD => {
+                    self.computer.peek(index)
+                }
+                Some(("ROM", index)) if index < cpu_emulator::computer::ROM_SIZE => {
+                    self.computer.rom()[index]
+                }
+                _ => return Err(ScriptError::UnknownVariable(variable.to_string())),
+            },
+        };
+
+        Ok(Value::Number(value as i16))
+    }
+
+    fn set(&mut self, variable: &str, value: i16) -> Result<(), ScriptError> {
+        let value = value as u16;
+        match variable {
+            "A" => self.computer.set_a(value),
+            "D" => self.computer.set_d(value),
+            "PC" => self.computer.set_pc(value),
+            _ => match indexed_variable(variable) {
+                Some(("RAM", index)) if index <= cpu_emulator::computer::KBD => {
+                    self.computer.poke(index, value)
+                }
+                _ => return Err(ScriptError::UnknownVariable(variable.to_string())),
+            },
+        }
+
+        Ok(())
+    }
+
+    fn execute(&mut self, command: &str) -> Result<bool, ScriptError> {
+        match command {
+            "ticktock" | "tock" => self.computer.step(),
+            "tick" => {}
+            _ => return Ok(false),
+        }
+
+        Ok(true)
+    }
+}
+
+/// The VM emulator: runs a `.vm` file, or every `.vm` file in a directory.
+#[derive(Default)]
+pub struct VmSimulator {
+    emulator: Option<VmEmulator>,
+}
+
+impl VmSimulator {
+    fn emulator(&self) -> Result<&VmEmulator, ScriptError> {
+        self.emulator
+            .as_ref()
+            .ok_or_else(|| ScriptError::Load(String::new(), "no program loaded".to_string()))
+    }
+
+    /// The RAM address behind a VM emulator variable: the pointers (`sp`,
+    /// `local`, ...), `RAM[i]`, `temp[i]`, and segment entries such as
+    /// `local[i]`.
+    fn address(&self, variable: &str) -> Result<usize, ScriptError> {
+        let emulator = self.emulator()?;
+        let address = match variable {
+            "sp" => Some(SP),
+            "local" => Some(LCL),
+            "argument" => Some(ARG),
+            "this" => Some(THIS),
+            "that" => Some(THAT),
+            _ => match indexed_variable(variable) {
+                Some(("RAM", index)) => Some(index),
+                Some(("temp", index)) if index < 8 => Some(TEMP + index),
+                Some(("local", index)) => Some(emulator.peek(LCL) as usize + index),
+                Some(("argument", index)) => Some(emulator.peek(ARG) as usize + index),
+                Some(("this", index)) => Some(emulator.peek(THIS) as usize + index),
+                Some(("that", index)) => Some(emulator.peek(THAT) as usize + index),
+                _ => None,
+            },
+        };
+
+        address
+            .filter(|address| *address < MEMORY_SIZE)
+            .ok_or_else(|| ScriptError::UnknownVariable(variable.to_string()))
+    }
+}
+
+impl Simulator for VmSimulator {
+    fn load(&mut self, path: &Path) -> Result<(), ScriptError> {
+        let display = path.display().to_string();
+        let input_filepaths =
+            collect_input_filepaths(path).map_err(|e| ScriptError::Io(display.clone(), e))?;
+
+        let mut parser = Parser::without_writer(input_filepaths);
+        let commands = parser
+            .commands()
+            .map_err(|e| ScriptError::Io(display.clone(), e))?;
+        if !parser.diagnostics().is_empty() {
+            return Err(ScriptError::Load(display, parser.diagnostics().to_string()));
+        }
+
+        let mut emulator =
+            VmEmulator::new(commands).map_err(|e| ScriptError::Load(display, e.to_string()))?;
+
+        // Like the course's VM emulator, programs with a Sys.init start
+        // there directly; the script sets up the stack itself
+        if let Some(index) = emulator.function_index("Sys.init") {
+            emulator.set_pc(index);
+        }
+        self.emulator = Some(emulator);
+
+        Ok(())
+    }
+
+    fn get(&self, variable: &str) -> Result<Value, ScriptError> {
+        let address = self.address(variable)?;
+
+        Ok(Value::Number(self.emulator()?.peek(address) as i16))
+    }
+
+    fn set(&mut self, variable: &str, value: i16) -> Result<(), ScriptError> {
+        let address = self.address(variable)?;
+        if let Some(emulator) = self.emulator.as_mut() {
+            emulator.poke(address, value as u16);
+        }
+
+        Ok(())
+    }
+
+    fn execute(&mut self, command: &str) -> Result<bool, ScriptError> {
+        if command != "vmstep" {
+            return Ok(false);
+        }
+
+        if let Some(emulator) = self.emulator.as_mut() {
+            emulator
+                .step()
+                .map_err(|e| ScriptError::Runtime(e.to_string()))?;
+        }
+
+        Ok(true)
+    }
+}
@@ -0,0 +1,251 @@
+use crate::generator::{HEAP_BASE, HEAP_SIZE, STATIC_COUNT};
+use crate::program::Program;
+use cpu_emulator::{Computer, RunOutcome};
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use vm_translator::code_writer::CodeWriter;
+use vm_translator::emulator::{self, VmEmulator};
+use vm_translator::parser::Parser;
+
+const MAX_VM_STEPS: u64 = 200_000;
+const MAX_CPU_CYCLES: u64 = 20_000_000;
+
+/// The bootstrap frame starts with Sys.init's return address: a command
+/// index in the VM emulator but a ROM address on the CPU.
+const RETURN_ADDRESS_SLOT: usize = 256;
+
+/// Translator settings a program is checked under.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Config {
+    pub optimize: bool,
+    pub shared_subroutines: bool,
+}
+
+impl Config {
+    pub const ALL: [Config; 4] = [
+        Config {
+            optimize: false,
+            shared_subroutines: false,
+        },
+        Config {
+            optimize: true,
+            shared_subroutines: false,
+        },
+        Config {
+            optimize: false,
+            shared_subroutines: true,
+        },
+        Config {
+            optimize: true,
+            shared_subroutines: true,
+        },
+    ];
+}
+
+impl fmt::Display for Config {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut flags = Vec::new();
+        if self.optimize {
+            flags.push("--optimize");
+        }
+        if self.shared_subroutines {
+            flags.push("--shared-subroutines");
+        }
+
+        match flags.is_empty() {
+            true => write!(f, "default"),
+            false => write!(f, "{}", flags.join(" ")),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Failure {
+    /// The translator or assembler rejected the program
+    Translation(String),
+    /// The reference run itself failed, which points at the generator
+    Emulator(String),
+    NoHalt(&'static str),
+    Mismatch {
+        location: String,
+        expected: u16,
+        actual: u16,
+    },
+}
+
+impl fmt::Display for Failure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Failure::Translation(message) => write!(f, "translation failed: {}", message),
+            Failure::Emulator(message) => write!(f, "VM emulator failed: {}", message),
+            Failure::NoHalt(side) => write!(f, "{} did not halt", side),
+            Failure::Mismatch {
+                location,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "{}: VM emulator has {}, translated program has {}",
+                location, *expected as i16, *actual as i16
+            ),
+        }
+    }
+}
+
+/// A scratch directory per check, removed again when dropped.
+struct ScratchDir(PathBuf);
+
+impl ScratchDir {
+    fn new() -> Result<Self, Failure> {
+        static NEXT: AtomicUsize = AtomicUsize::new(0);
+
+        let path = std::env::temp_dir().join(format!(
+            "vm-fuzz-{}-{}",
+            process::id(),
+            NEXT.fetch_add(1, Ordering::Relaxed)
+        ));
+        fs::create_dir_all(&path).map_err(|e| Failure::Translation(e.to_string()))?;
+
+        Ok(Self(path))
+    }
+}
+
+impl Drop for ScratchDir {
+    fn drop(&mut self) {
+        fs::remove_dir_all(&self.0).ok();
+    }
+}
+
+fn write_files(program: &Program, dir: &Path) -> Result<Vec<String>, Failure> {
+    program
+        .files()
+        .into_iter()
+        .map(|(name, source)| {
+            let path = dir.join(name);
+            fs::write(&path, source).map_err(|e| Failure::Translation(e.to_string()))?;
+            Ok(path.to_string_lossy().to_string())
+        })
+        .collect()
+}
+
+fn run_emulator(program: &Program, input_filepaths: Vec<String>) -> Result<VmEmulator, Failure> {
+    let mut parser = Parser::without_writer(input_filepaths);
+    let commands = parser
+        .commands()
+        .map_err(|e| Failure::Emulator(e.to_string()))?;
+    if !parser.diagnostics().is_empty() {
+        return Err(Failure::Emulator(parser.diagnostics().to_string()));
+    }
+
+    let mut vm = VmEmulator::new(commands).map_err(|e| Failure::Emulator(e.to_string()))?;
+    vm.poke(emulator::THIS, HEAP_BASE);
+    vm.poke(emulator::THAT, HEAP_BASE);
+    for (i, value) in program.heap.iter().enumerate() {
+        vm.poke(HEAP_BASE as usize + i, *value);
+    }
+
+    let outcome = vm
+        .bootstrap()
+        .and_then(|_| vm.run(MAX_VM_STEPS))
+        .map_err(|e| Failure::Emulator(e.to_string()))?;
+    match outcome {
+        emulator::RunOutcome::Halted { .. } => Ok(vm),
+        emulator::RunOutcome::StepLimit { .. } => Err(Failure::NoHalt("VM emulator")),
+    }
+}
+
+fn run_translated(
+    program: &Program,
+    input_filepaths: Vec<String>,
+    dir: &Path,
+    config: Config,
+) -> Result<Computer, Failure> {
+    let output = dir.join("Fuzz.asm");
+    let mut writer = CodeWriter::new(output.to_string_lossy().to_string());
+    writer.set_optimize(config.optimize);
+    writer.set_shared_subroutines(config.shared_subroutines);
+
+    let mut parser = Parser::new(input_filepaths, writer);
+    parser
+        .parse(true)
+        .map_err(|e| Failure::Translation(e.to_string()))?;
+    if !parser.diagnostics().is_empty() {
+        return Err(Failure::Translation(parser.diagnostics().to_string()));
+    }
+
+    let asm = fs::read_to_string(&output).map_err(|e| Failure::Translation(e.to_string()))?;
+    let words = hack_asm::assemble(&asm)
+        .map_err(|diagnostics| Failure::Translation(diagnostics.to_string()))?
+        .words;
+
+    let mut computer = Computer::new(&words).map_err(|e| Failure::Translation(e.to_string()))?;
+    computer.poke(emulator::THIS, HEAP_BASE);
+    computer.poke(emulator::THAT, HEAP_BASE);
+    for (i, value) in program.heap.iter().enumerate() {
+        computer.poke(HEAP_BASE as usize + i, *value);
+    }
+
+    match computer.run(MAX_CPU_CYCLES) {
+        RunOutcome::Halted { .. } => Ok(computer),
+        RunOutcome::CycleLimit { .. } => Err(Failure::NoHalt("translated program")),
+    }
+}
+
+/// Everything a VM program can observe once Sys.init halts: the pointers,
+/// temp, statics, the live stack and the heap window. Memory above SP is
+/// left out since the optimizer may skip stores there.
+fn observed(vm: &VmEmulator) -> Vec<(String, usize)> {
+    let mut locations: Vec<(String, usize)> = ["SP", "LCL", "ARG", "THIS", "THAT"]
+        .iter()
+        .enumerate()
+        .map(|(address, name)| (name.to_string(), address))
+        .collect();
+
+    locations.extend((0..8).map(|i| (format!("temp {}", i), emulator::TEMP + i)));
+
+    for file in [Program::INIT_FILE, Program::HELPER_FILE] {
+        for i in 0..STATIC_COUNT {
+            if let Some(address) = vm.static_address(file, i) {
+                locations.push((format!("static {} of {}", i, file), address));
+            }
+        }
+    }
+
+    let sp = vm.peek(emulator::SP) as usize;
+    locations.extend(
+        (RETURN_ADDRESS_SLOT + 1..sp).map(|address| (format!("stack RAM[{}]", address), address)),
+    );
+
+    let heap = HEAP_BASE as usize;
+    locations.extend(
+        (heap..heap + HEAP_SIZE).map(|address| (format!("heap RAM[{}]", address), address)),
+    );
+
+    locations
+}
+
+/// Runs `program` in the VM emulator and, translated under `config`, on
+/// the CPU emulator, and reports the first observable difference.
+pub fn check(program: &Program, config: Config) -> Result<(), Failure> {
+    let dir = ScratchDir::new()?;
+    let input_filepaths = write_files(program, &dir.0)?;
+
+    let vm = run_emulator(program, input_filepaths.clone())?;
+    let computer = run_translated(program, input_filepaths, &dir.0, config)?;
+
+    for (location, address) in observed(&vm) {
+        let (expected, actual) = (vm.peek(address), computer.peek(address));
+        if expected != actual {
+            return Err(Failure::Mismatch {
+                location,
+                expected,
+                actual,
+            });
+        }
+    }
+
+    Ok(())
+}
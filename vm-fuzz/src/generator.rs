@@ -0,0 +1,285 @@
+use crate::program::{Expr, Function, Program, Segment, Statement};
+
+/// Static and temp indices used, and the index range within `this`/`that`.
+pub const STATIC_COUNT: usize = 8;
+pub const TEMP_COUNT: usize = 8;
+pub const POINTED_COUNT: usize = 8;
+
+/// THIS and THAT are only ever pointed into `HEAP_BASE..HEAP_BASE +
+/// HEAP_POINTERS`, so `this`/`that` stay inside a window the harness
+/// compares.
+pub const HEAP_BASE: u16 = 3000;
+pub const HEAP_POINTERS: u16 = 32;
+pub const HEAP_SIZE: usize = HEAP_POINTERS as usize + POINTED_COUNT;
+
+const MAX_FUNCTIONS: usize = 4;
+const MAX_ARGS: usize = 3;
+const MAX_LOCALS: usize = 4;
+const MAX_STATEMENTS: usize = 6;
+const MAX_EXPR_DEPTH: usize = 4;
+const MAX_LOOP_DEPTH: usize = 2;
+const MAX_LOOP_COUNT: u64 = 3;
+
+const UNARY: [&str; 2] = ["neg", "not"];
+const BINARY: [&str; 7] = ["add", "sub", "and", "or", "eq", "lt", "gt"];
+
+/// Constants that sit on overflow and sign boundaries once negated or added.
+const EDGE_CONSTANTS: [u16; 6] = [0, 1, 2, 16384, 32766, 32767];
+
+/// xorshift64*: small, fast and reproducible from a seed.
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        // The state must never be zero
+        Self(seed.wrapping_mul(0x9E37_79B9_7F4A_7C15) | 1)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    /// Uniform in `0..bound`.
+    pub fn below(&mut self, bound: u64) -> u64 {
+        self.next_u64() % bound
+    }
+
+    pub fn chance(&mut self, percent: u64) -> bool {
+        self.below(100) < percent
+    }
+
+    pub fn pick<T: Copy>(&mut self, items: &[T]) -> T {
+        items[self.below(items.len() as u64) as usize]
+    }
+}
+
+/// Shape of the function being generated. A loop nested `depth` deep
+/// counts in `local depth`, so locals below `depth` are never popped into.
+struct Scope {
+    index: usize,
+    n_args: usize,
+    n_locals: usize,
+    depth: usize,
+}
+
+pub struct Generator<'a> {
+    rng: &'a mut Rng,
+    /// Argument count of every function, decided up front so calls can be
+    /// generated before their callee's body
+    signatures: Vec<usize>,
+}
+
+impl<'a> Generator<'a> {
+    pub fn new(rng: &'a mut Rng) -> Self {
+        Self {
+            rng,
+            signatures: Vec::new(),
+        }
+    }
+
+    pub fn program(&mut self) -> Program {
+        let n_functions = 1 + self.rng.below(MAX_FUNCTIONS as u64) as usize;
+
+        // Sys.init is called with no arguments
+        self.signatures = (0..n_functions)
+            .map(|i| {
+                if i == 0 {
+                    0
+                } else {
+                    self.rng.below(MAX_ARGS as u64 + 1) as usize
+                }
+            })
+            .collect();
+
+        let functions = (0..n_functions)
+            .map(|index| {
+                let n_args = self.signatures[index];
+                // Room for the counters of nested loops on top of plain locals
+                let n_locals = MAX_LOOP_DEPTH + self.rng.below(MAX_LOCALS as u64 + 1) as usize;
+                let mut scope = Scope {
+                    index,
+                    n_args,
+                    n_locals,
+                    depth: 0,
+                };
+
+                let body = self.statements(&mut scope);
+                let result = self.expr(&scope, 0);
+                let name = if index == 0 {
+                    "Sys.init".to_string()
+                } else {
+                    format!("Fuzz.f{}", index)
+                };
+
+                Function {
+                    name,
+                    n_args,
+                    n_locals,
+                    body,
+                    result,
+                }
+            })
+            .collect();
+
+        let heap = (0..HEAP_SIZE)
+            .map(|_| self.rng.below(0x10000) as u16)
+            .collect();
+
+        Program { functions, heap }
+    }
+
+    fn statements(&mut self, scope: &mut Scope) -> Vec<Statement> {
+        let count = 1 + self.rng.below(MAX_STATEMENTS as u64) as usize;
+
+        (0..count).map(|_| self.statement(scope)).collect()
+    }
+
+    fn statement(&mut self, scope: &mut Scope) -> Statement {
+        match self.rng.below(10) {
+            0 if scope.depth < MAX_LOOP_DEPTH => {
+                let counter = scope.depth;
+                let count = 1 + self.rng.below(MAX_LOOP_COUNT) as u16;
+                scope.depth += 1;
+                let body = self.statements(scope);
+                scope.depth -= 1;
+
+                Statement::Loop {
+                    counter,
+                    count,
+                    body,
+                }
+            }
+            1 if scope.depth < MAX_LOOP_DEPTH => {
+                let condition = self.expr(scope, 0);
+                // Counts towards the nesting limit to keep programs small
+                scope.depth += 1;
+                let body = self.statements(scope);
+                scope.depth -= 1;
+
+                Statement::If(condition, body)
+            }
+            2 => {
+                let which = self.rng.below(2) as usize;
+                let address = HEAP_BASE + self.rng.below(HEAP_POINTERS as u64) as u16;
+
+                Statement::SetPointer(which, address)
+            }
+            _ => {
+                let (segment, index) = self.pop_target(scope);
+
+                Statement::Pop(self.expr(scope, 0), segment, index)
+            }
+        }
+    }
+
+    fn pop_target(&mut self, scope: &Scope) -> (Segment, usize) {
+        loop {
+            let segment = self.rng.pick(&[
+                Segment::Local,
+                Segment::Argument,
+                Segment::Static,
+                Segment::Temp,
+                Segment::This,
+                Segment::That,
+            ]);
+            if let Some(index) = self.index(scope, segment) {
+                // Loop counters are only ever written by their loop
+                if segment == Segment::Local && index < scope.depth {
+                    continue;
+                }
+                return (segment, index);
+            }
+        }
+    }
+
+    fn index(&mut self, scope: &Scope, segment: Segment) -> Option<usize> {
+        let count = match segment {
+            Segment::Local => scope.n_locals,
+            Segment::Argument => scope.n_args,
+            Segment::Static => STATIC_COUNT,
+            Segment::Temp => TEMP_COUNT,
+            Segment::This | Segment::That => POINTED_COUNT,
+            Segment::Pointer => 2,
+        };
+
+        (count > 0).then(|| self.rng.below(count as u64) as usize)
+    }
+
+    fn constant(&mut self) -> u16 {
+        if self.rng.chance(40) {
+            self.rng.pick(&EDGE_CONSTANTS)
+        } else {
+            self.rng.below(0x8000) as u16
+        }
+    }
+
+    /// One of the signed 16-bit extremes, where `x - y` overflows if the
+    /// other operand has the opposite sign.
+    fn extreme(&mut self) -> Expr {
+        let constant = |value: u16| Box::new(Expr::Constant(value));
+
+        match self.rng.below(6) {
+            0 => Expr::Constant(0),
+            1 => Expr::Constant(1),
+            2 => Expr::Constant(32767),
+            3 => Expr::Unary("neg", constant(1)),
+            4 => Expr::Unary("neg", constant(32767)),
+            _ => Expr::Binary(
+                "sub",
+                Box::new(Expr::Unary("neg", constant(32767))),
+                constant(1),
+            ),
+        }
+    }
+
+    fn expr(&mut self, scope: &Scope, depth: usize) -> Expr {
+        let leaf = depth >= MAX_EXPR_DEPTH;
+        let callees = scope.index + 1..self.signatures.len();
+
+        match self.rng.below(10) {
+            0..=2 if !leaf => {
+                let operation = self.rng.pick(&BINARY);
+                let (left, right) = if ["lt", "gt"].contains(&operation) && self.rng.chance(30) {
+                    (self.extreme(), self.extreme())
+                } else {
+                    (self.expr(scope, depth + 1), self.expr(scope, depth + 1))
+                };
+
+                Expr::Binary(operation, Box::new(left), Box::new(right))
+            }
+            3 if !leaf => {
+                let operation = self.rng.pick(&UNARY);
+
+                Expr::Unary(operation, Box::new(self.expr(scope, depth + 1)))
+            }
+            4 if !leaf && !callees.is_empty() => {
+                let callee = callees.start + self.rng.below(callees.len() as u64) as usize;
+                let args = (0..self.signatures[callee])
+                    .map(|_| self.expr(scope, depth + 1))
+                    .collect();
+
+                Expr::Call(callee, args)
+            }
+            5..=6 => {
+                let segment = self.rng.pick(&[
+                    Segment::Local,
+                    Segment::Argument,
+                    Segment::Static,
+                    Segment::Temp,
+                    Segment::This,
+                    Segment::That,
+                    Segment::Pointer,
+                ]);
+
+                match self.index(scope, segment) {
+                    Some(index) => Expr::Push(segment, index),
+                    None => Expr::Constant(self.constant()),
+                }
+            }
+            _ => Expr::Constant(self.constant()),
+        }
+    }
+}
@@ -0,0 +1,9 @@
+pub mod differential;
+pub mod generator;
+pub mod minimizer;
+pub mod program;
+
+pub use differential::{Config, Failure, check};
+pub use generator::{Generator, Rng};
+pub use minimizer::minimize;
+pub use program::Program;
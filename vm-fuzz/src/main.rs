@@ -0,0 +1,78 @@
+use std::env;
+use std::mem;
+use std::process;
+use std::time::{SystemTime, UNIX_EPOCH};
+use vm_fuzz::{Config, Generator, Rng, check, minimize};
+
+const DEFAULT_ITERATIONS: u64 = 200;
+
+fn main() {
+    let (flags, args): (Vec<String>, Vec<String>) =
+        env::args().skip(1).partition(|arg| arg.starts_with("--"));
+    if args.len() > 1 {
+        println!("Usage: vm-fuzz [iterations] [--seed=N]");
+        return;
+    }
+
+    let iterations: u64 = match args.first() {
+        Some(iterations) => iterations.parse().expect("iterations"),
+        None => DEFAULT_ITERATIONS,
+    };
+
+    let mut seed: u64 = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|time| time.as_secs())
+        .unwrap_or_default();
+    for flag in &flags {
+        match flag.split_once('=') {
+            Some(("--seed", value)) => seed = value.parse().expect("seed"),
+            _ => {
+                println!("Unknown option: {}", flag);
+                return;
+            }
+        }
+    }
+
+    // Each program gets its own seed so a failure can be replayed alone
+    // with --seed and an iteration count of 1
+    for program_seed in seed..seed + iterations {
+        let program = Generator::new(&mut Rng::new(program_seed)).program();
+
+        for config in Config::ALL {
+            let Err(failure) = check(&program, config) else {
+                continue;
+            };
+
+            println!("seed {} failed with {}: {}", program_seed, config, failure);
+
+            // Only shrink towards the same kind of failure, so a candidate
+            // that merely breaks differently isn't taken for the bug
+            let kind = mem::discriminant(&failure);
+            let minimized = minimize(&program, |candidate| {
+                check(candidate, config).is_err_and(|failure| mem::discriminant(&failure) == kind)
+            });
+            let failure = check(&minimized, config).expect_err("minimized program fails");
+
+            println!(
+                "minimized from {} to {} nodes: {}",
+                program.size(),
+                minimized.size(),
+                failure
+            );
+            for (name, source) in minimized.files() {
+                if !source.is_empty() {
+                    println!("// {}\n{}", name, source);
+                }
+            }
+            process::exit(1);
+        }
+    }
+
+    println!(
+        "{} programs agree under all {} translator configurations (seeds {}..{})",
+        iterations,
+        Config::ALL.len(),
+        seed,
+        seed + iterations
+    );
+}
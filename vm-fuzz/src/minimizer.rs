@@ -0,0 +1,143 @@
+use crate::program::{Expr, Program, Statement};
+
+/// Greedily shrinks `program` while `fails` keeps returning true: drops
+/// statements, unwraps loops and ifs, and replaces expressions by one of
+/// their operands or `push constant 0`. Every candidate is well formed, so
+/// a failure that survives is still a translator bug.
+pub fn minimize(program: &Program, fails: impl Fn(&Program) -> bool) -> Program {
+    let mut smallest = program.clone();
+
+    'shrink: loop {
+        for candidate in candidates(&smallest) {
+            if fails(&candidate) {
+                smallest = candidate;
+                continue 'shrink;
+            }
+        }
+
+        return smallest;
+    }
+}
+
+/// Programs one simplification step away from `program`.
+fn candidates(program: &Program) -> Vec<Program> {
+    let mut candidates = Vec::new();
+
+    for (i, function) in program.functions.iter().enumerate() {
+        for body in statements_variants(&function.body) {
+            let mut candidate = program.clone();
+            candidate.functions[i].body = body;
+            candidates.push(candidate);
+        }
+
+        for result in expr_variants(&function.result) {
+            let mut candidate = program.clone();
+            candidate.functions[i].result = result;
+            candidates.push(candidate);
+        }
+    }
+
+    // Initial heap contents only matter when a read depends on them
+    if program.heap.iter().any(|value| *value != 0) {
+        let mut candidate = program.clone();
+        candidate.heap.fill(0);
+        candidates.push(candidate);
+    }
+
+    candidates
+}
+
+fn statements_variants(statements: &[Statement]) -> Vec<Vec<Statement>> {
+    let mut variants = Vec::new();
+
+    for (i, statement) in statements.iter().enumerate() {
+        let replace = |replacement: Vec<Statement>| {
+            let mut variant = statements[..i].to_vec();
+            variant.extend(replacement);
+            variant.extend_from_slice(&statements[i + 1..]);
+            variant
+        };
+
+        variants.push(replace(Vec::new()));
+
+        match statement {
+            Statement::Pop(expr, segment, index) => {
+                for expr in expr_variants(expr) {
+                    variants.push(replace(vec![Statement::Pop(expr, *segment, *index)]));
+                }
+            }
+            Statement::SetPointer(..) => {}
+            Statement::Loop {
+                counter,
+                count,
+                body,
+            } => {
+                variants.push(replace(body.clone()));
+                if *count > 1 {
+                    variants.push(replace(vec![Statement::Loop {
+                        counter: *counter,
+                        count: 1,
+                        body: body.clone(),
+                    }]));
+                }
+                for body in statements_variants(body) {
+                    variants.push(replace(vec![Statement::Loop {
+                        counter: *counter,
+                        count: *count,
+                        body,
+                    }]));
+                }
+            }
+            Statement::If(condition, body) => {
+                variants.push(replace(body.clone()));
+                for condition in expr_variants(condition) {
+                    variants.push(replace(vec![Statement::If(condition, body.clone())]));
+                }
+                for body in statements_variants(body) {
+                    variants.push(replace(vec![Statement::If(condition.clone(), body)]));
+                }
+            }
+        }
+    }
+
+    variants
+}
+
+fn expr_variants(expr: &Expr) -> Vec<Expr> {
+    let mut variants = Vec::new();
+    if *expr != Expr::Constant(0) {
+        variants.push(Expr::Constant(0));
+    }
+
+    match expr {
+        Expr::Constant(_) | Expr::Push(..) => {}
+        Expr::Unary(operation, operand) => {
+            variants.push(operand.as_ref().clone());
+            for operand in expr_variants(operand) {
+                variants.push(Expr::Unary(operation, Box::new(operand)));
+            }
+        }
+        Expr::Binary(operation, left, right) => {
+            variants.push(left.as_ref().clone());
+            variants.push(right.as_ref().clone());
+            for left in expr_variants(left) {
+                variants.push(Expr::Binary(operation, Box::new(left), right.clone()));
+            }
+            for right in expr_variants(right) {
+                variants.push(Expr::Binary(operation, left.clone(), Box::new(right)));
+            }
+        }
+        Expr::Call(callee, args) => {
+            variants.extend(args.iter().cloned());
+            for (i, arg) in args.iter().enumerate() {
+                for arg in expr_variants(arg) {
+                    let mut args = args.clone();
+                    args[i] = arg;
+                    variants.push(Expr::Call(*callee, args));
+                }
+            }
+        }
+    }
+
+    variants
+}
@@ -0,0 +1,290 @@
+use std::fmt::Write;
+
+/// Segments a generated program reads from; pops never target `constant`
+/// or `pointer`, see [`Statement::SetPointer`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Segment {
+    Local,
+    Argument,
+    Static,
+    Temp,
+    This,
+    That,
+    Pointer,
+}
+
+impl Segment {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Segment::Local => "local",
+            Segment::Argument => "argument",
+            Segment::Static => "static",
+            Segment::Temp => "temp",
+            Segment::This => "this",
+            Segment::That => "that",
+            Segment::Pointer => "pointer",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    Constant(u16),
+    Push(Segment, usize),
+    Unary(&'static str, Box<Expr>),
+    Binary(&'static str, Box<Expr>, Box<Expr>),
+    /// Call of `functions[index]`, which always has a higher index than the
+    /// caller so programs can't recurse
+    Call(usize, Vec<Expr>),
+}
+
+/// Every statement leaves the stack as deep as it found it, so any of them
+/// can be dropped or unwrapped and the program stays well formed.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Statement {
+    Pop(Expr, Segment, usize),
+    /// Points THIS (0) or THAT (1) into the fuzzed heap window
+    SetPointer(usize, u16),
+    /// Runs `body` `count` times, counting down in the reserved `local`
+    Loop {
+        counter: usize,
+        count: u16,
+        body: Vec<Statement>,
+    },
+    If(Expr, Vec<Statement>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Function {
+    pub name: String,
+    pub n_args: usize,
+    pub n_locals: usize,
+    pub body: Vec<Statement>,
+    pub result: Expr,
+}
+
+/// `functions[0]` is `Sys.init`, which halts instead of returning; the rest
+/// are written to a second file so statics of both files get exercised.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Program {
+    pub functions: Vec<Function>,
+    /// Initial contents of the heap window THIS and THAT point into
+    pub heap: Vec<u16>,
+}
+
+impl Program {
+    pub const INIT_FILE: &'static str = "Sys.vm";
+    pub const HELPER_FILE: &'static str = "Fuzz.vm";
+
+    /// Function indices reachable from `Sys.init`; minimizing can leave
+    /// helpers that nothing calls any more.
+    fn reachable(&self) -> Vec<bool> {
+        let mut reachable = vec![false; self.functions.len()];
+        reachable[0] = true;
+
+        // Calls only go to higher indices, so one pass in order is enough
+        for i in 0..self.functions.len() {
+            if !reachable[i] {
+                continue;
+            }
+
+            let function = &self.functions[i];
+            let mut calls = Vec::new();
+            for statement in &function.body {
+                statement.calls(&mut calls);
+            }
+            function.result.calls(&mut calls);
+
+            for callee in calls {
+                reachable[callee] = true;
+            }
+        }
+
+        reachable
+    }
+
+    /// The `.vm` files of the program as `(file name, source)` pairs.
+    pub fn files(&self) -> Vec<(&'static str, String)> {
+        let reachable = self.reachable();
+        let mut init = String::new();
+        let mut helpers = String::new();
+
+        for (i, function) in self.functions.iter().enumerate() {
+            if !reachable[i] {
+                continue;
+            }
+
+            let out = if i == 0 { &mut init } else { &mut helpers };
+            let mut writer = FunctionWriter {
+                program: self,
+                out,
+                labels: 0,
+            };
+            writer.function(function, i == 0);
+        }
+
+        vec![(Program::HELPER_FILE, helpers), (Program::INIT_FILE, init)]
+    }
+
+    /// Number of expression and statement nodes, a rough size for reports.
+    pub fn size(&self) -> usize {
+        self.functions
+            .iter()
+            .map(|function| {
+                function.body.iter().map(Statement::size).sum::<usize>() + function.result.size()
+            })
+            .sum()
+    }
+}
+
+impl Expr {
+    fn calls(&self, calls: &mut Vec<usize>) {
+        match self {
+            Expr::Constant(_) | Expr::Push(..) => {}
+            Expr::Unary(_, operand) => operand.calls(calls),
+            Expr::Binary(_, left, right) => {
+                left.calls(calls);
+                right.calls(calls);
+            }
+            Expr::Call(index, args) => {
+                calls.push(*index);
+                for arg in args {
+                    arg.calls(calls);
+                }
+            }
+        }
+    }
+
+    fn size(&self) -> usize {
+        match self {
+            Expr::Constant(_) | Expr::Push(..) => 1,
+            Expr::Unary(_, operand) => 1 + operand.size(),
+            Expr::Binary(_, left, right) => 1 + left.size() + right.size(),
+            Expr::Call(_, args) => 1 + args.iter().map(Expr::size).sum::<usize>(),
+        }
+    }
+}
+
+impl Statement {
+    fn calls(&self, calls: &mut Vec<usize>) {
+        match self {
+            Statement::Pop(expr, ..) => expr.calls(calls),
+            Statement::SetPointer(..) => {}
+            Statement::Loop { body, .. } => body.iter().for_each(|s| s.calls(calls)),
+            Statement::If(condition, body) => {
+                condition.calls(calls);
+                body.iter().for_each(|s| s.calls(calls));
+            }
+        }
+    }
+
+    fn size(&self) -> usize {
+        match self {
+            Statement::Pop(expr, ..) => 1 + expr.size(),
+            Statement::SetPointer(..) => 1,
+            Statement::Loop { body, .. } => 1 + body.iter().map(Statement::size).sum::<usize>(),
+            Statement::If(condition, body) => {
+                1 + condition.size() + body.iter().map(Statement::size).sum::<usize>()
+            }
+        }
+    }
+}
+
+struct FunctionWriter<'a> {
+    program: &'a Program,
+    out: &'a mut String,
+    labels: usize,
+}
+
+impl FunctionWriter<'_> {
+    fn line(&mut self, line: &str) {
+        writeln!(self.out, "{}", line).expect("write to string");
+    }
+
+    fn label(&mut self, prefix: &str) -> String {
+        self.labels += 1;
+        format!("{}_{}", prefix, self.labels)
+    }
+
+    fn function(&mut self, function: &Function, halt: bool) {
+        self.line(&format!("function {} {}", function.name, function.n_locals));
+        for statement in &function.body {
+            self.statement(statement);
+        }
+
+        self.expr(&function.result);
+        if halt {
+            self.line("pop temp 0");
+            self.line("label HALT");
+            self.line("goto HALT");
+        } else {
+            self.line("return");
+        }
+    }
+
+    fn statement(&mut self, statement: &Statement) {
+        match statement {
+            Statement::Pop(expr, segment, index) => {
+                self.expr(expr);
+                self.line(&format!("pop {} {}", segment.name(), index));
+            }
+            Statement::SetPointer(which, address) => {
+                self.line(&format!("push constant {}", address));
+                self.line(&format!("pop pointer {}", which));
+            }
+            Statement::Loop {
+                counter,
+                count,
+                body,
+            } => {
+                let label = self.label("LOOP");
+                self.line(&format!("push constant {}", count));
+                self.line(&format!("pop local {}", counter));
+                self.line(&format!("label {}", label));
+                for statement in body {
+                    self.statement(statement);
+                }
+                self.line(&format!("push local {}", counter));
+                self.line("push constant 1");
+                self.line("sub");
+                self.line(&format!("pop local {}", counter));
+                self.line(&format!("push local {}", counter));
+                self.line(&format!("if-goto {}", label));
+            }
+            Statement::If(condition, body) => {
+                let (then_label, end_label) = (self.label("IF_TRUE"), self.label("IF_END"));
+                self.expr(condition);
+                self.line(&format!("if-goto {}", then_label));
+                self.line(&format!("goto {}", end_label));
+                self.line(&format!("label {}", then_label));
+                for statement in body {
+                    self.statement(statement);
+                }
+                self.line(&format!("label {}", end_label));
+            }
+        }
+    }
+
+    fn expr(&mut self, expr: &Expr) {
+        match expr {
+            Expr::Constant(value) => self.line(&format!("push constant {}", value)),
+            Expr::Push(segment, index) => self.line(&format!("push {} {}", segment.name(), index)),
+            Expr::Unary(operation, operand) => {
+                self.expr(operand);
+                self.line(operation);
+            }
+            Expr::Binary(operation, left, right) => {
+                self.expr(left);
+                self.expr(right);
+                self.line(operation);
+            }
+            Expr::Call(index, args) => {
+                for arg in args {
+                    self.expr(arg);
+                }
+                let callee = &self.program.functions[*index];
+                self.line(&format!("call {} {}", callee.name, callee.n_args));
+            }
+        }
+    }
+}
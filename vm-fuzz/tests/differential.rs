@@ -0,0 +1,21 @@
+use vm_fuzz::{Config, Generator, Rng, check};
+
+/// Enough programs to cover every statement and expression kind while
+/// keeping `cargo test` quick; `vm-fuzz` runs open-ended searches.
+const SEEDS: std::ops::Range<u64> = 1..101;
+
+#[test]
+fn translated_programs_match_the_emulator() {
+    for seed in SEEDS {
+        let program = Generator::new(&mut Rng::new(seed)).program();
+
+        for config in Config::ALL {
+            if let Err(failure) = check(&program, config) {
+                panic!(
+                    "seed {} failed with {}: {} (replay with `vm-fuzz 1 --seed={}`)",
+                    seed, config, failure, seed
+                );
+            }
+        }
+    }
+}